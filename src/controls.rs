@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use crate::game_manager::{AppState, spawn_screen_backdrop, despawn_screen};
+use crate::data_file::{data_file_path, read_header, read_data_file, write_data_file};
+
+pub struct ControlsPlugin;
+impl Plugin for ControlsPlugin{
+    fn build(&self, app: &mut App){
+        app
+            // Loaded here rather than in a startup system so the title screen and tips already show the player's keys
+            .insert_resource(load_key_bindings())
+            .insert_resource(ControlsSelection { row: 0, listening: false })
+            .add_event::<RedrawControlsEvent>()
+            .add_systems(OnEnter(AppState::Controls), spawn_controls_screen)
+            .add_systems(OnExit(AppState::Controls), (despawn_screen::<ControlsScreen>, despawn_screen::<ControlsRowText>, save_key_bindings))
+            .add_systems(Update, (detect_controls_input, draw_controls_rows).chain().run_if(in_state(AppState::Controls)));
+    }
+}
+
+const CONTROLS_FILE_VERSION: u32 = 1;
+const CONTROLS_FILE_HEADER: &str = "tetris-controls";
+const MAX_KEYS_PER_ACTION: usize = 3;
+
+// Everything the player can bind a key to
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    SonicDrop,
+    RotateClockwise,
+    RotateCounterClockwise,
+    Rotate180,
+    Hold,
+    Pause,
+    Restart,
+    ToggleTips,
+}
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::SoftDrop,
+        Action::HardDrop,
+        Action::SonicDrop,
+        Action::RotateClockwise,
+        Action::RotateCounterClockwise,
+        Action::Rotate180,
+        Action::Hold,
+        Action::Pause,
+        Action::Restart,
+        Action::ToggleTips,
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            Action::MoveLeft => "Move Left",
+            Action::MoveRight => "Move Right",
+            Action::SoftDrop => "Soft Drop",
+            Action::HardDrop => "Hard Drop",
+            Action::SonicDrop => "Sonic Drop",
+            Action::RotateClockwise => "Rotate Clockwise",
+            Action::RotateCounterClockwise => "Rotate Counter Clockwise",
+            Action::Rotate180 => "Rotate 180",
+            Action::Hold => "Hold",
+            Action::Pause => "Pause",
+            Action::Restart => "Restart",
+            Action::ToggleTips => "Show/Hide Tips",
+        }
+    }
+
+    // Stable name for the controls file
+    pub fn key(&self) -> &str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::SoftDrop => "soft_drop",
+            Action::HardDrop => "hard_drop",
+            Action::SonicDrop => "sonic_drop",
+            Action::RotateClockwise => "rotate_clockwise",
+            Action::RotateCounterClockwise => "rotate_counter_clockwise",
+            Action::Rotate180 => "rotate_180",
+            Action::Hold => "hold",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::ToggleTips => "toggle_tips",
+        }
+    }
+
+    // Pause is the only way off the pause screen and Restart the only way to start over, so they always keep a key
+    pub fn needs_a_key(&self) -> bool {
+        matches!(self, Action::Pause | Action::Restart)
+    }
+
+    pub fn default_keys(&self) -> Vec<KeyCode> {
+        match self {
+            Action::MoveLeft => vec![KeyCode::ArrowLeft],
+            Action::MoveRight => vec![KeyCode::ArrowRight],
+            Action::SoftDrop => vec![KeyCode::ArrowDown],
+            Action::HardDrop => vec![KeyCode::Space],
+            Action::SonicDrop => vec![KeyCode::KeyS],
+            Action::RotateClockwise => vec![KeyCode::ArrowUp],
+            Action::RotateCounterClockwise => vec![KeyCode::ControlLeft],
+            Action::Rotate180 => vec![KeyCode::KeyA],
+            Action::Hold => vec![KeyCode::KeyC],
+            Action::Pause => vec![KeyCode::Escape, KeyCode::KeyP],
+            Action::Restart => vec![KeyCode::KeyR],
+            Action::ToggleTips => vec![KeyCode::KeyH],
+        }
+    }
+}
+
+// Keys that can be bound, which is also every key the controls file can name
+const BINDABLE_KEYS: [KeyCode; 75] = [
+    KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF, KeyCode::KeyG,
+    KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL, KeyCode::KeyM, KeyCode::KeyN,
+    KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR, KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU,
+    KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX, KeyCode::KeyY, KeyCode::KeyZ,
+    KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+    KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    KeyCode::Numpad0, KeyCode::Numpad1, KeyCode::Numpad2, KeyCode::Numpad3, KeyCode::Numpad4,
+    KeyCode::Numpad5, KeyCode::Numpad6, KeyCode::Numpad7, KeyCode::Numpad8, KeyCode::Numpad9,
+    KeyCode::ArrowLeft, KeyCode::ArrowRight, KeyCode::ArrowUp, KeyCode::ArrowDown,
+    KeyCode::Space, KeyCode::Enter, KeyCode::Escape, KeyCode::Tab, KeyCode::Backspace,
+    KeyCode::ShiftLeft, KeyCode::ShiftRight, KeyCode::ControlLeft, KeyCode::ControlRight, KeyCode::AltLeft, KeyCode::AltRight,
+    KeyCode::Comma, KeyCode::Period, KeyCode::Slash, KeyCode::Semicolon, KeyCode::Quote,
+    KeyCode::BracketLeft, KeyCode::BracketRight, KeyCode::Backslash, KeyCode::Minus, KeyCode::Equal, KeyCode::Backquote,
+    KeyCode::Home, KeyCode::End, KeyCode::Delete,
+];
+
+pub fn key_name(key: KeyCode) -> String {
+    // Short names for the screen, the controls file uses the KeyCode names instead
+    match key {
+        KeyCode::ArrowLeft => "Left".to_string(),
+        KeyCode::ArrowRight => "Right".to_string(),
+        KeyCode::ArrowUp => "Up".to_string(),
+        KeyCode::ArrowDown => "Down".to_string(),
+        KeyCode::ShiftLeft => "Left Shift".to_string(),
+        KeyCode::ShiftRight => "Right Shift".to_string(),
+        KeyCode::ControlLeft => "Left Ctrl".to_string(),
+        KeyCode::ControlRight => "Right Ctrl".to_string(),
+        KeyCode::AltLeft => "Left Alt".to_string(),
+        KeyCode::AltRight => "Right Alt".to_string(),
+        KeyCode::Escape => "ESC".to_string(),
+        KeyCode::Enter => "ENTER".to_string(),
+        KeyCode::Space => "SPACE".to_string(),
+        _ => {
+            let name = format!("{:?}", key);
+            name.strip_prefix("Key").or(name.strip_prefix("Digit")).unwrap_or(&name).to_string()
+        }
+    }
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.into_iter().find(|key| format!("{:?}", key) == name)
+}
+
+// Every action's keys, any one of them triggers it
+#[derive(Resource, Clone, Debug)]
+pub struct KeyBindings(pub HashMap<Action, Vec<KeyCode>>);
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings(Action::ALL.iter().map(|action| (*action, action.default_keys())).collect())
+    }
+}
+impl KeyBindings {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.0.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+
+    pub fn pressed(&self, keyboard_input: &ButtonInput<KeyCode>, action: Action) -> bool {
+        keyboard_input.any_pressed(self.keys(action).iter().copied())
+    }
+
+    pub fn just_pressed(&self, keyboard_input: &ButtonInput<KeyCode>, action: Action) -> bool {
+        keyboard_input.any_just_pressed(self.keys(action).iter().copied())
+    }
+
+    // "Left Ctrl / Z", or "unbound"
+    pub fn describe(&self, action: Action) -> String {
+        let keys = self.keys(action);
+        if keys.is_empty() {
+            return "unbound".to_string();
+        }
+        keys.iter().map(|key| key_name(*key)).collect::<Vec<String>>().join(" / ")
+    }
+
+    pub fn bind(&mut self, action: Action, key: KeyCode) -> bool {
+        // A key only ever does one thing, so it's taken off whatever it was bound to before,
+        // unless it's the last key of an action that needs one
+        let is_last_needed_key = self.0.iter()
+            .any(|(bound_action, keys)| *bound_action != action && bound_action.needs_a_key() && keys.as_slice() == [key]);
+        if is_last_needed_key {
+            return false;
+        }
+        for keys in self.0.values_mut() {
+            keys.retain(|bound_key| *bound_key != key);
+        }
+        let keys = self.0.entry(action).or_default();
+        keys.push(key);
+        if keys.len() > MAX_KEYS_PER_ACTION {
+            keys.remove(0);
+        }
+        true
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        // Actions that need a key keep the one added last
+        let keys = self.0.entry(action).or_default();
+        let kept_key = if action.needs_a_key() { keys.last().copied() } else { None };
+        *keys = kept_key.into_iter().collect();
+    }
+}
+
+// Saving and loading
+fn controls_path() -> Option<PathBuf> {
+    data_file_path("controls.txt")
+}
+
+pub fn parse_key_bindings(contents: &str) -> Result<KeyBindings, String> {
+    // Same layout as the high score file: a header and version, then one action=Key,Key line per action.
+    // Actions missing from the file keep their default keys, unknown actions and keys are skipped
+    let lines = read_header(contents, CONTROLS_FILE_HEADER, CONTROLS_FILE_VERSION)?;
+
+    let mut key_bindings = KeyBindings::default();
+    for line in lines {
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        let Some(action) = Action::ALL.into_iter().find(|action| action.key() == name.trim()) else {
+            continue;
+        };
+        let keys: Vec<KeyCode> = value.split(',').filter_map(|key| parse_key(key.trim())).take(MAX_KEYS_PER_ACTION).collect();
+        if keys.is_empty() && action.needs_a_key() {
+            continue;
+        }
+        key_bindings.0.insert(action, keys);
+    }
+    Ok(key_bindings)
+}
+
+pub fn serialize_key_bindings(key_bindings: &KeyBindings) -> String {
+    let mut contents = format!("{} {}\n", CONTROLS_FILE_HEADER, CONTROLS_FILE_VERSION);
+    for action in Action::ALL {
+        let keys: Vec<String> = key_bindings.keys(action).iter().map(|key| format!("{:?}", key)).collect();
+        contents.push_str(&format!("{}={}\n", action.key(), keys.join(",")));
+    }
+    contents
+}
+
+pub fn load_key_bindings() -> KeyBindings {
+    let Some(path) = controls_path() else {
+        warn!("No data directory found, controls won't be saved");
+        return KeyBindings::default();
+    };
+    // Without a file the defaults have never been changed
+    read_data_file(&path, parse_key_bindings).unwrap_or_default()
+}
+
+pub fn save_key_bindings(
+    key_bindings: Res<KeyBindings>
+){
+    if let Some(path) = controls_path() {
+        write_data_file(&path, &serialize_key_bindings(&key_bindings));
+    }
+}
+
+// Controls screen
+#[derive(Resource)]
+pub struct ControlsSelection {
+    pub row: usize,
+    pub listening: bool, // The next key pressed gets added to the chosen action
+}
+
+#[derive(Component)]
+pub struct ControlsScreen;
+
+#[derive(Component)]
+pub struct ControlsRowText;
+
+#[derive(Event)]
+pub struct RedrawControlsEvent;
+
+pub fn spawn_controls_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    window_query: Query<&Window>,
+    mut controls_selection: ResMut<ControlsSelection>,
+    mut redraw_controls_event: EventWriter<RedrawControlsEvent>,
+){
+    controls_selection.listening = false;
+
+    let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+    spawn_screen_backdrop(&mut commands, &window_query, ControlsScreen {});
+
+    commands.spawn((
+        Text2d::new("Controls"),
+        TextColor(Color::srgb(0.8, 0.85, 0.9)),
+        TextFont {
+            font: font.clone(),
+            font_size: 80.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(0.0, 380.0, 51.0),
+        ControlsScreen {}
+    ));
+
+    commands.spawn((
+        Text2d::new("Up/Down to choose, ENTER to add a key, BACKSPACE to clear, DELETE to reset all\nESC to save and go back"),
+        TextColor(Color::srgb(0.8, 0.85, 0.9)),
+        TextFont {
+            font: font.clone(),
+            font_size: 20.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(0.0, -380.0, 51.0),
+        ControlsScreen {}
+    ));
+
+    redraw_controls_event.send(RedrawControlsEvent);
+}
+
+pub fn detect_controls_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut controls_selection: ResMut<ControlsSelection>,
+    mut key_bindings: ResMut<KeyBindings>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut redraw_controls_event: EventWriter<RedrawControlsEvent>,
+){
+    let action = Action::ALL[controls_selection.row];
+
+    // Waiting for a key, every bindable key is taken as is, even ESC. Pause and Restart's last keys can't be
+    // taken away, so pressing one keeps waiting for another
+    if controls_selection.listening {
+        if let Some(key) = BINDABLE_KEYS.into_iter().find(|key| keyboard_input.just_pressed(*key))
+            && key_bindings.bind(action, key)
+        {
+            controls_selection.listening = false;
+            redraw_controls_event.send(RedrawControlsEvent);
+        }
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Title);
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        controls_selection.row = (controls_selection.row + Action::ALL.len() - 1) % Action::ALL.len();
+        redraw_controls_event.send(RedrawControlsEvent);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        controls_selection.row = (controls_selection.row + 1) % Action::ALL.len();
+        redraw_controls_event.send(RedrawControlsEvent);
+    }
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        controls_selection.listening = true;
+        redraw_controls_event.send(RedrawControlsEvent);
+    }
+    if keyboard_input.just_pressed(KeyCode::Backspace) {
+        key_bindings.clear(action);
+        redraw_controls_event.send(RedrawControlsEvent);
+    }
+    if keyboard_input.just_pressed(KeyCode::Delete) {
+        *key_bindings = KeyBindings::default();
+        redraw_controls_event.send(RedrawControlsEvent);
+    }
+}
+
+pub fn draw_controls_rows(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    controls_selection: Res<ControlsSelection>,
+    key_bindings: Res<KeyBindings>,
+    controls_row_text_query: Query<Entity, With<ControlsRowText>>,
+    mut redraw_controls_event: EventReader<RedrawControlsEvent>,
+){
+    if !redraw_controls_event.is_empty() {
+        redraw_controls_event.clear();
+
+        for entity in controls_row_text_query.iter() {
+            commands.entity(entity).despawn();
+        }
+
+        let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+        let text_font = TextFont {
+            font: font.clone(),
+            font_size: 30.0,
+            ..default()
+        };
+        let text_gap = 50.0;
+        let mut text_y = 270.0;
+
+        for (index, action) in Action::ALL.iter().enumerate() {
+            let (text, text_color) = if index != controls_selection.row {
+                (format!("{}: {}", action.name(), key_bindings.describe(*action)), TextColor(Color::srgb(0.8, 0.85, 0.9)))
+            } else if controls_selection.listening {
+                (format!("< {}: press a key >", action.name()), TextColor(Color::srgb(1.0, 0.85, 0.2)))
+            } else {
+                (format!("< {}: {} >", action.name(), key_bindings.describe(*action)), TextColor(Color::srgb(1.0, 0.85, 0.2)))
+            };
+
+            commands.spawn((
+                Text2d::new(text),
+                text_color,
+                text_font.clone(),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Transform::from_xyz(0.0, text_y, 51.0),
+                ControlsRowText {}
+            ));
+
+            text_y -= text_gap;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_and_restart_always_keep_a_key() {
+        let mut key_bindings = KeyBindings::default();
+        key_bindings.clear(Action::Pause);
+        assert_eq!(key_bindings.keys(Action::Pause), &[KeyCode::KeyP]);
+        key_bindings.clear(Action::Hold);
+        assert!(key_bindings.keys(Action::Hold).is_empty());
+
+        // Their last key can't be taken by another action, any other key can
+        assert!(!key_bindings.bind(Action::Hold, KeyCode::KeyP));
+        assert!(!key_bindings.bind(Action::Hold, KeyCode::KeyR));
+        assert!(key_bindings.bind(Action::Hold, KeyCode::Escape));
+        assert_eq!(key_bindings.keys(Action::Pause), &[KeyCode::KeyP]);
+        assert_eq!(key_bindings.keys(Action::Hold), &[KeyCode::Escape]);
+
+        // A second key can move between them, the last one stays put
+        assert!(key_bindings.bind(Action::Pause, KeyCode::Escape));
+        assert!(key_bindings.bind(Action::Restart, KeyCode::Escape));
+        assert_eq!(key_bindings.keys(Action::Pause), &[KeyCode::KeyP]);
+        assert!(!key_bindings.bind(Action::Restart, KeyCode::KeyP));
+
+        let loaded = parse_key_bindings("tetris-controls 1\npause=\nrestart=\nhold=\n").unwrap();
+        assert_eq!(loaded.keys(Action::Pause), Action::Pause.default_keys().as_slice());
+        assert_eq!(loaded.keys(Action::Restart), Action::Restart.default_keys().as_slice());
+        assert!(loaded.keys(Action::Hold).is_empty());
+    }
+}
\ No newline at end of file
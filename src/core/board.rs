@@ -0,0 +1,69 @@
+use crate::core::piece::{Piece, TetrominoLetter};
+
+pub const BOARD_WIDTH: usize = 10;
+pub const BOARD_HEIGHT: usize = 20; // Visible rows
+pub const BOARD_HIDDEN_HEIGHT: usize = 6; // Rows above the visible board that pieces spawn into
+pub const BOARD_TOTAL_HEIGHT: usize = BOARD_HEIGHT + BOARD_HIDDEN_HEIGHT;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cell {
+    Empty,
+    Filled(TetrominoLetter), // Which piece the cell came from, so the front end can colour it
+}
+
+// Every locked in cell. Row 0 is the bottom, so y increases going up
+#[derive(Clone, Debug)]
+pub struct Board {
+    pub cells: Vec<Cell>, // Row by row from the bottom, see Board::index
+}
+impl Board {
+    pub fn new() -> Self {
+        Board { cells: vec![Cell::Empty; BOARD_WIDTH * BOARD_TOTAL_HEIGHT] }
+    }
+
+    pub fn index(x: i32, y: i32) -> usize {
+        y as usize * BOARD_WIDTH + x as usize
+    }
+
+    pub fn is_inside(x: i32, y: i32) -> bool {
+        x >= 0 && x < BOARD_WIDTH as i32 && y >= 0 && y < BOARD_TOTAL_HEIGHT as i32
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<Cell> {
+        if Board::is_inside(x, y) { Some(self.cells[Board::index(x, y)]) } else { None }
+    }
+
+    // Walls, the floor, the ceiling and locked in cells all block a piece
+    pub fn is_blocked(&self, x: i32, y: i32) -> bool {
+        self.get(x, y).is_none_or(|cell| cell != Cell::Empty)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(|cell| *cell == Cell::Empty)
+    }
+
+    pub fn place(&mut self, piece: &Piece) {
+        for (x, y) in piece.cells() {
+            if Board::is_inside(x, y) {
+                self.cells[Board::index(x, y)] = Cell::Filled(piece.letter);
+            }
+        }
+    }
+
+    pub fn clear_full_rows(&mut self) -> usize {
+        // Drain full rows from the top down so the indices below stay valid, then top the board back up
+        let full_rows: Vec<usize> = (0..BOARD_TOTAL_HEIGHT)
+            .filter(|row| self.cells[row * BOARD_WIDTH..(row + 1) * BOARD_WIDTH].iter().all(|cell| *cell != Cell::Empty))
+            .collect();
+        for row in full_rows.iter().rev() {
+            self.cells.drain(row * BOARD_WIDTH..(row + 1) * BOARD_WIDTH);
+        }
+        self.cells.resize(BOARD_WIDTH * BOARD_TOTAL_HEIGHT, Cell::Empty);
+        full_rows.len()
+    }
+}
+impl Default for Board {
+    fn default() -> Self {
+        Board::new()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,867 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use rand::{SeedableRng, rngs::StdRng};
+use crate::core::board::{Board, BOARD_HEIGHT, BOARD_HIDDEN_HEIGHT};
+use crate::core::piece::{Piece, TetrominoLetter};
+use crate::core::randomizer::{Randomizer, RandomizerKind};
+use crate::core::rotation::{RotationDirection, RotationSystem, RotationSystemKind, try_rotate, detect_t_spin};
+use crate::core::scoring::{Scoring, ScoringRules, GameStats, LevelUpRule, TSpin, is_difficult_clear, calculate_perfect_clear_bonus};
+
+pub const FRAMES_PER_SECOND: f32 = 60.0;
+pub const TWENTY_G: f32 = 20.0; // Rows per frame, fast enough to cross the whole board in one frame
+pub const MAX_LOCK_RESETS: usize = 15;
+
+// Everything a player can do in a tick
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Input {
+    Shift(i32), // Cells to move, negative is left
+    SoftDrop(usize), // Rows to move down
+    RotateClockwise,
+    RotateCounterClockwise,
+    Rotate180,
+    HardDrop,
+    SonicDrop, // Drops to the floor like hard drop but leaves the piece free to move until it locks
+    Hold,
+}
+
+// Everything that happened during a tick, in order
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameEvent {
+    PieceSpawned(TetrominoLetter),
+    PieceMoved, // The active piece moved, rotated or fell
+    PieceHeld(TetrominoLetter),
+    PieceLocked { lines_cleared: usize, t_spin: TSpin },
+    TSpin { t_spin: TSpin, lines_cleared: usize },
+    PerfectClear { lines_cleared: usize, back_to_back: bool }, // back_to_back is a tetris perfect clear that continued a chain
+    ScoreChanged,
+    LevelUp(usize),
+    ToppedOut,
+    GoalReached,
+}
+
+// What ends a game other than topping out
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ModeGoal {
+    None,
+    Level(usize), // Finished once this level is completed
+    Lines(usize),
+    TimeLimit(Duration),
+}
+
+// Which gravity each level gets
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GravityCurve {
+    Guideline,
+    Classic, // The original table, maxes out at a row every frame
+    TwentyG, // Pieces land the moment they spawn
+}
+impl GravityCurve {
+    pub fn next(&self) -> Self {
+        match self {
+            GravityCurve::Guideline => GravityCurve::Classic,
+            GravityCurve::Classic => GravityCurve::TwentyG,
+            GravityCurve::TwentyG => GravityCurve::Guideline,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        match self {
+            GravityCurve::Guideline => GravityCurve::TwentyG,
+            GravityCurve::Classic => GravityCurve::Guideline,
+            GravityCurve::TwentyG => GravityCurve::Classic,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GravityCurve::Guideline => "Guideline",
+            GravityCurve::Classic => "Classic",
+            GravityCurve::TwentyG => "20G",
+        }
+    }
+
+    pub fn rows_per_frame(&self, level: usize) -> f32 {
+        let rows_per_frame = match self {
+            GravityCurve::Guideline => {
+                // Seconds per row is (0.8 - ((level - 1) * 0.007))^(level - 1)
+                let level = level.max(1) as f32 - 1.0;
+                let seconds_per_row = (0.8 - (level * 0.007)).max(0.0).powf(level);
+                if seconds_per_row > 0.0 { 1.0 / (seconds_per_row * FRAMES_PER_SECOND) } else { TWENTY_G }
+            }
+            GravityCurve::Classic => 1.0 / (gravity_seconds_for_level(level) * FRAMES_PER_SECOND),
+            GravityCurve::TwentyG => TWENTY_G,
+        };
+        rows_per_frame.min(TWENTY_G)
+    }
+
+    pub fn lock_delay_seconds(&self, level: usize) -> f32 {
+        // Lock delay only starts shrinking on the levels after this curve reaches 20G
+        match (1..=level).find(|&level| self.rows_per_frame(level) >= TWENTY_G) {
+            Some(twenty_g_level) => (0.5 - (level - twenty_g_level) as f32 * 0.05).max(0.25),
+            None => 0.5,
+        }
+    }
+}
+
+pub fn gravity_seconds_for_level(level: usize) -> f32 {
+    // Seconds per row for each level on the classic curve
+    match level {
+        1 => 1.0, // OG 1.0
+        2 => 0.8,
+        3 => 0.6,
+        4 => 0.4,
+        5 => 0.3,
+        6 => 0.25,
+        7 => 0.2,
+        8 => 0.15,
+        9 => 0.1,
+        10 => 0.05,
+        _ => 0.01667, // Max gravity
+    }
+}
+
+// How fast pieces fall, in rows per 1/60 s frame
+#[derive(Clone, Debug)]
+pub struct Gravity {
+    pub rows_per_frame: f32,
+    pub accumulated_rows: f32, // Fractions of a row carried over between ticks
+}
+impl Gravity {
+    pub fn reset(&mut self) {
+        self.accumulated_rows = 0.0;
+    }
+
+    pub fn seconds_per_row(&self) -> f32 {
+        1.0 / (self.rows_per_frame * FRAMES_PER_SECOND)
+    }
+
+    pub fn is_twenty_g(&self) -> bool {
+        self.rows_per_frame >= TWENTY_G
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LockDownMode {
+    Infinite, // Every move or rotation resets the lock delay
+    ExtendedPlacement, // Moves and rotations reset the lock delay up to MAX_LOCK_RESETS times per row
+    StepReset, // Only falling a row resets the lock delay
+}
+impl LockDownMode {
+    pub fn next(&self) -> Self {
+        match self {
+            LockDownMode::Infinite => LockDownMode::ExtendedPlacement,
+            LockDownMode::ExtendedPlacement => LockDownMode::StepReset,
+            LockDownMode::StepReset => LockDownMode::Infinite,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        self.next().next()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LockDownMode::Infinite => "Infinite",
+            LockDownMode::ExtendedPlacement => "Extended Placement",
+            LockDownMode::StepReset => "Step Reset",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LockDown {
+    pub delay: Duration,
+    pub elapsed: Duration, // Only runs while the piece is grounded
+    pub resets_used: usize,
+    pub lowest_row: i32, // Lowest row the active piece has reached, reaching a new one gives the resets back
+}
+impl LockDown {
+    fn reset_for_piece(&mut self, piece: &Piece) {
+        self.elapsed = Duration::ZERO;
+        self.resets_used = 0;
+        self.lowest_row = piece.lowest_row();
+    }
+
+    fn reset_after_move(&mut self, mode: LockDownMode) {
+        // Called after a successful move or rotation
+        match mode {
+            LockDownMode::Infinite => self.elapsed = Duration::ZERO,
+            LockDownMode::ExtendedPlacement => {
+                if self.resets_used < MAX_LOCK_RESETS {
+                    // Only count resets once the piece has touched down and the timer is running
+                    if self.elapsed > Duration::ZERO {
+                        self.resets_used += 1;
+                    }
+                    self.elapsed = Duration::ZERO;
+                }
+            }
+            LockDownMode::StepReset => {}
+        }
+    }
+
+    fn reset_after_fall(&mut self, mode: LockDownMode) {
+        // Called after the piece drops at least a row. Step reset counts every row, even one it already
+        // reached before a kick lifted it, the other modes only reset on a new lowest row
+        if mode == LockDownMode::StepReset {
+            self.elapsed = Duration::ZERO;
+        }
+    }
+
+    fn is_out_of_resets(&self, mode: LockDownMode) -> bool {
+        mode == LockDownMode::ExtendedPlacement && self.resets_used >= MAX_LOCK_RESETS
+    }
+}
+
+// Rules picked before a game starts
+#[derive(Clone, Debug)]
+pub struct GameSettings {
+    pub start_level: usize,
+    pub level_up_rule: LevelUpRule,
+    pub level_cap: Option<usize>, // Highest level that's shown and scored, the goal ends the game there
+    pub gravity_curve: GravityCurve,
+    pub lock_down_mode: LockDownMode,
+    pub scoring_rules: ScoringRules,
+    pub goal: ModeGoal,
+    pub randomizer: RandomizerKind,
+    pub rotation_system: RotationSystemKind,
+    pub preview_count: usize, // Upcoming pieces the player can see, the queue always holds one more
+    pub seed: u64, // The same seed always deals the same pieces
+}
+impl Default for GameSettings {
+    fn default() -> Self {
+        GameSettings {
+            start_level: 1,
+            level_up_rule: LevelUpRule::Fixed,
+            level_cap: None,
+            gravity_curve: GravityCurve::Guideline,
+            lock_down_mode: LockDownMode::ExtendedPlacement,
+            scoring_rules: ScoringRules::guideline(),
+            goal: ModeGoal::None,
+            randomizer: RandomizerKind::SevenBag,
+            rotation_system: RotationSystemKind::Srs,
+            preview_count: 5,
+            seed: 0,
+        }
+    }
+}
+
+// One game from the first piece to the end. Call start, then tick every frame with whatever the player did
+#[derive(Clone, Debug)]
+pub struct Game {
+    pub settings: GameSettings,
+    pub board: Board,
+    pub active: Option<Piece>,
+    pub queue: VecDeque<TetrominoLetter>,
+    pub held: Option<TetrominoLetter>,
+    pub can_hold: bool, // Only one hold is allowed until the next piece locks in
+    pub scoring: Scoring,
+    pub stats: GameStats,
+    pub gravity: Gravity,
+    pub lock_down: LockDown,
+    pub is_over: bool,
+    pub rotation_system: Box<dyn RotationSystem>,
+    randomizer: Box<dyn Randomizer>,
+    rng: StdRng,
+    pending_inputs: Vec<Input>, // Inputs from the tick a piece locked that belong to the next piece
+    events: Vec<GameEvent>,
+}
+
+impl Game {
+    pub fn new(settings: GameSettings) -> Self {
+        let mut scoring = Scoring::new(settings.start_level);
+        scoring.level = capped_level(&settings, settings.start_level);
+        let level = scoring.level;
+        let mut game = Game {
+            board: Board::new(),
+            active: None,
+            queue: VecDeque::new(),
+            held: None,
+            can_hold: true,
+            scoring,
+            stats: GameStats::default(),
+            gravity: Gravity { rows_per_frame: settings.gravity_curve.rows_per_frame(level), accumulated_rows: 0.0 },
+            lock_down: LockDown {
+                delay: Duration::from_secs_f32(settings.gravity_curve.lock_delay_seconds(level)),
+                elapsed: Duration::ZERO,
+                resets_used: 0,
+                lowest_row: i32::MAX,
+            },
+            is_over: false,
+            rotation_system: settings.rotation_system.create(),
+            randomizer: settings.randomizer.create(),
+            rng: StdRng::seed_from_u64(settings.seed),
+            pending_inputs: vec![],
+            events: vec![],
+            settings,
+        };
+        game.fill_queue();
+        game
+    }
+
+    pub fn start(&mut self) -> Vec<GameEvent> {
+        self.spawn_next_piece();
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn tick(&mut self, delta: Duration, inputs: &[Input]) -> Vec<GameEvent> {
+        if self.is_over {
+            return vec![];
+        }
+        self.stats.play_time += delta;
+
+        // Whatever was left over from a lock last tick goes before this tick's inputs
+        let mut inputs_to_apply = std::mem::take(&mut self.pending_inputs);
+        inputs_to_apply.extend_from_slice(inputs);
+
+        let pieces_placed = self.stats.pieces_placed;
+        for (index, input) in inputs_to_apply.iter().enumerate() {
+            if self.active.is_none() {
+                break;
+            }
+            // Anything after a lock belongs to the next piece, which gets it first thing next tick
+            if self.stats.pieces_placed != pieces_placed {
+                self.pending_inputs = inputs_to_apply[index..].to_vec();
+                break;
+            }
+            self.apply_input(*input);
+        }
+
+        // A piece that just spawned from a hard drop starts falling next tick
+        if !self.is_over && self.stats.pieces_placed == pieces_placed {
+            self.apply_gravity(delta);
+            self.update_lock_down(delta);
+        }
+        self.check_time_limit();
+        std::mem::take(&mut self.events)
+    }
+
+    // The piece's landing spot, for drawing a ghost
+    pub fn ghost(&self) -> Option<Piece> {
+        self.active.as_ref().map(|piece| {
+            let mut ghost = piece.clone();
+            ghost.position.1 -= piece.drop_distance(&self.board) as i32;
+            ghost
+        })
+    }
+
+    fn apply_input(&mut self, input: Input) {
+        let Some(mut piece) = self.active.clone() else {
+            return;
+        };
+        match input {
+            Input::Shift(cells) => {
+                // As many cells as DAS/ARR asked for, stopping at the first wall or cell in the way
+                let direction = cells.signum();
+                let mut moved = false;
+                for _ in 0..cells.abs() {
+                    if !piece.can_move(&self.board, direction, 0) {
+                        break;
+                    }
+                    piece.position.0 += direction;
+                    moved = true;
+                }
+                if moved {
+                    piece.last_rotation_kick = None;
+                    self.active = Some(piece);
+                    self.lock_down.reset_after_move(self.settings.lock_down_mode);
+                    self.events.push(GameEvent::PieceMoved);
+                }
+            }
+            Input::SoftDrop(rows) => {
+                let rows = rows.min(piece.drop_distance(&self.board));
+                if rows > 0 {
+                    piece.position.1 -= rows as i32;
+                    piece.last_rotation_kick = None;
+                    self.active = Some(piece);
+                    self.lock_down.reset_after_fall(self.settings.lock_down_mode);
+                    self.scoring.score += rows * self.settings.scoring_rules.soft_drop_points_per_row;
+                    self.gravity.reset();
+                    self.events.push(GameEvent::PieceMoved);
+                    self.events.push(GameEvent::ScoreChanged);
+                }
+            }
+            Input::RotateClockwise | Input::RotateCounterClockwise | Input::Rotate180 => {
+                let direction = match input {
+                    Input::RotateClockwise => RotationDirection::Clockwise,
+                    Input::RotateCounterClockwise => RotationDirection::CounterClockwise,
+                    _ => RotationDirection::Half,
+                };
+                if let Some(rotated) = try_rotate(&piece, &self.board, self.rotation_system.as_ref(), direction) {
+                    self.active = Some(rotated);
+                    self.lock_down.reset_after_move(self.settings.lock_down_mode);
+                    self.events.push(GameEvent::PieceMoved);
+                }
+            }
+            Input::HardDrop => {
+                let rows = piece.drop_distance(&self.board);
+                if rows > 0 {
+                    // Only counts as a move if the piece actually dropped
+                    piece.position.1 -= rows as i32;
+                    piece.last_rotation_kick = None;
+                    self.scoring.score += rows * self.settings.scoring_rules.hard_drop_points_per_row;
+                    self.events.push(GameEvent::ScoreChanged);
+                }
+                // Lock straight away instead of waiting out the lock delay
+                self.active = Some(piece);
+                self.lock_piece();
+            }
+            Input::SonicDrop => {
+                let rows = piece.drop_distance(&self.board);
+                if rows > 0 {
+                    piece.position.1 -= rows as i32;
+                    piece.last_rotation_kick = None;
+                    self.active = Some(piece);
+                    self.lock_down.reset_after_fall(self.settings.lock_down_mode);
+                    self.scoring.score += rows * self.settings.scoring_rules.soft_drop_points_per_row;
+                    self.events.push(GameEvent::PieceMoved);
+                    self.events.push(GameEvent::ScoreChanged);
+                }
+                self.gravity.reset();
+            }
+            Input::Hold => self.hold(),
+        }
+    }
+
+    fn hold(&mut self) {
+        if !self.can_hold {
+            return;
+        }
+        let Some(piece) = self.active.take() else {
+            return;
+        };
+
+        // Swap with the held piece, or pull the next one from the queue if nothing is held yet
+        let previously_held = self.held.replace(piece.letter);
+        self.can_hold = false;
+        self.events.push(GameEvent::PieceHeld(piece.letter));
+        match previously_held {
+            Some(letter) => self.spawn_piece(letter),
+            None => self.spawn_next_piece(),
+        }
+    }
+
+    fn apply_gravity(&mut self, delta: Duration) {
+        let Some(mut piece) = self.active.clone() else {
+            return;
+        };
+
+        // Build up fractions of a row every tick, drop every whole row that's due
+        self.gravity.accumulated_rows += self.gravity.rows_per_frame * delta.as_secs_f32() * FRAMES_PER_SECOND;
+        let rows_due = if self.gravity.is_twenty_g() { BOARD_HEIGHT + BOARD_HIDDEN_HEIGHT } else { self.gravity.accumulated_rows.floor() as usize };
+        self.gravity.accumulated_rows -= self.gravity.accumulated_rows.floor();
+
+        let rows = rows_due.min(piece.drop_distance(&self.board));
+        if rows > 0 {
+            piece.position.1 -= rows as i32;
+            piece.last_rotation_kick = None;
+            self.lock_down.reset_after_fall(self.settings.lock_down_mode);
+            self.events.push(GameEvent::PieceMoved);
+        }
+
+        // Resting on something doesn't save up rows for when it slides off
+        if piece.is_grounded(&self.board) {
+            self.gravity.reset();
+        }
+        self.active = Some(piece);
+    }
+
+    fn update_lock_down(&mut self, delta: Duration) {
+        let Some(piece) = self.active.as_ref() else {
+            return;
+        };
+
+        // Falling to a new lowest row resets the lock delay in every mode
+        let lowest_row = piece.lowest_row();
+        if lowest_row < self.lock_down.lowest_row {
+            self.lock_down.lowest_row = lowest_row;
+            self.lock_down.resets_used = 0;
+            self.lock_down.elapsed = Duration::ZERO;
+        }
+
+        if piece.is_grounded(&self.board) {
+            if self.lock_down.is_out_of_resets(self.settings.lock_down_mode) {
+                // No resets left, so the piece locks as soon as it touches down
+                self.lock_down.elapsed = self.lock_down.delay;
+            }
+            self.lock_down.elapsed += delta;
+            if self.lock_down.elapsed >= self.lock_down.delay {
+                self.lock_piece();
+            }
+        }
+    }
+
+    fn lock_piece(&mut self) {
+        let Some(piece) = self.active.take() else {
+            return;
+        };
+
+        // Check for a T-spin and a lock out before the piece becomes part of the board
+        let t_spin = detect_t_spin(&piece, &self.board);
+        let is_locked_out = piece.is_locked_out();
+        self.board.place(&piece);
+
+        let lines_cleared = self.board.clear_full_rows();
+        self.scoring.lines_cleared += lines_cleared;
+
+        // Every lock is scored, even ones that clear nothing, since they break the combo
+        self.scoring.score_lock(lines_cleared, t_spin);
+        self.stats.record_lock(lines_cleared, self.scoring.combo);
+        let back_to_back = is_difficult_clear(lines_cleared, t_spin) && self.scoring.back_to_back.is_some_and(|chain| chain > 0);
+        self.scoring.goal_points += self.settings.level_up_rule.award(lines_cleared, t_spin, back_to_back);
+        self.events.push(GameEvent::PieceLocked { lines_cleared, t_spin });
+
+        // Perfect clear when the lines that were just cleared leave nothing on the board
+        if lines_cleared > 0 && self.board.is_empty() {
+            let back_to_back = lines_cleared == 4 && back_to_back;
+            self.scoring.score += calculate_perfect_clear_bonus(lines_cleared, self.scoring.level, back_to_back);
+            self.events.push(GameEvent::PerfectClear { lines_cleared, back_to_back });
+        }
+        if t_spin != TSpin::None {
+            self.events.push(GameEvent::TSpin { t_spin, lines_cleared });
+        }
+        self.update_level();
+        self.events.push(GameEvent::ScoreChanged);
+
+        self.can_hold = true;
+        if is_locked_out {
+            // The final board stays as it is, nothing spawns on top of it
+            self.end(GameEvent::ToppedOut);
+        } else if self.is_goal_reached() {
+            self.end(GameEvent::GoalReached);
+        } else {
+            self.spawn_next_piece();
+        }
+    }
+
+    fn update_level(&mut self) {
+        let level = capped_level(&self.settings, self.settings.level_up_rule.level_for(&self.scoring));
+        if level > self.scoring.level {
+            self.scoring.level = level;
+            self.gravity.rows_per_frame = self.settings.gravity_curve.rows_per_frame(level);
+            self.lock_down.delay = Duration::from_secs_f32(self.settings.gravity_curve.lock_delay_seconds(level));
+            self.events.push(GameEvent::LevelUp(level));
+        }
+    }
+
+    fn is_goal_reached(&self) -> bool {
+        match self.settings.goal {
+            ModeGoal::None | ModeGoal::TimeLimit(_) => false,
+            // The level shown is capped, so work out what it would have been
+            ModeGoal::Level(last_level) => self.settings.level_up_rule.level_for(&self.scoring) > last_level,
+            ModeGoal::Lines(target_lines) => self.scoring.lines_cleared >= target_lines,
+        }
+    }
+
+    fn check_time_limit(&mut self) {
+        if let ModeGoal::TimeLimit(limit) = self.settings.goal && !self.is_over && self.stats.play_time >= limit {
+            self.stats.play_time = limit;
+            self.end(GameEvent::GoalReached);
+        }
+    }
+
+    fn end(&mut self, event: GameEvent) {
+        self.is_over = true;
+        self.active = None;
+        self.events.push(event);
+    }
+
+    fn fill_queue(&mut self) {
+        while self.queue.len() < self.settings.preview_count + 1 {
+            let letter = self.randomizer.next(&mut self.rng);
+            self.queue.push_back(letter);
+        }
+    }
+
+    fn spawn_next_piece(&mut self) {
+        let letter = self.queue.pop_front().expect("the queue is refilled before it runs out");
+        self.fill_queue();
+        self.spawn_piece(letter);
+    }
+
+    fn spawn_piece(&mut self, letter: TetrominoLetter) {
+        let piece = Piece::new(letter, self.rotation_system.as_ref());
+        self.lock_down.reset_for_piece(&piece);
+        self.gravity.reset();
+        self.events.push(GameEvent::PieceSpawned(letter));
+
+        // Block out when the new piece overlaps cells that are already there
+        if piece.collides(&self.board) {
+            self.end(GameEvent::ToppedOut);
+            return;
+        }
+        self.active = Some(piece);
+        self.events.push(GameEvent::PieceMoved);
+    }
+}
+
+fn capped_level(settings: &GameSettings, level: usize) -> usize {
+    settings.level_cap.map_or(level, |level_cap| level.min(level_cap))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::board::{Cell, BOARD_WIDTH, BOARD_TOTAL_HEIGHT};
+
+    const FRAME: Duration = Duration::from_millis(100);
+
+    fn started_game(settings: GameSettings) -> Game {
+        let mut game = Game::new(settings);
+        game.start();
+        game
+    }
+
+    fn set_active(game: &mut Game, letter: TetrominoLetter) {
+        game.active = Some(Piece::new(letter, game.rotation_system.as_ref()));
+    }
+
+    fn fill(game: &mut Game, x: i32, y: i32) {
+        game.board.cells[Board::index(x, y)] = Cell::Filled(TetrominoLetter::O);
+    }
+
+    fn active_columns(game: &Game) -> (i32, i32) {
+        let piece = game.active.as_ref().unwrap();
+        (piece.cells().map(|(x, _)| x).min().unwrap(), piece.cells().map(|(x, _)| x).max().unwrap())
+    }
+
+    #[test]
+    fn walls_floor_and_cells_block_the_piece() {
+        let mut game = started_game(GameSettings::default());
+        set_active(&mut game, TetrominoLetter::T);
+
+        game.tick(Duration::ZERO, &[Input::Shift(-10)]);
+        assert_eq!(active_columns(&game).0, 0);
+
+        for y in 0..BOARD_TOTAL_HEIGHT as i32 {
+            fill(&mut game, 5, y);
+        }
+        game.tick(Duration::ZERO, &[Input::Shift(10)]);
+        assert_eq!(active_columns(&game).1, 4);
+
+        game.tick(Duration::ZERO, &[Input::SoftDrop(100)]);
+        assert_eq!(game.active.as_ref().unwrap().lowest_row(), 0);
+        assert!(game.active.as_ref().unwrap().is_grounded(&game.board));
+    }
+
+    #[test]
+    fn full_rows_are_cleared_and_the_rest_fall() {
+        let mut game = started_game(GameSettings::default());
+        set_active(&mut game, TetrominoLetter::I);
+        for x in (0..3).chain(7..BOARD_WIDTH as i32) {
+            fill(&mut game, x, 0);
+        }
+        fill(&mut game, 0, 1);
+
+        let events = game.tick(Duration::ZERO, &[Input::HardDrop]);
+        assert!(events.contains(&GameEvent::PieceLocked { lines_cleared: 1, t_spin: TSpin::None }));
+        assert_eq!(game.scoring.lines_cleared, 1);
+        assert!(game.board.is_blocked(0, 0));
+        assert!(!game.board.is_blocked(1, 0));
+        assert!(!game.board.is_blocked(0, 1));
+    }
+
+    #[test]
+    fn hold_only_works_once_per_piece() {
+        let mut game = started_game(GameSettings::default());
+        let first = game.active.as_ref().unwrap().letter;
+
+        let events = game.tick(Duration::ZERO, &[Input::Hold]);
+        assert!(events.contains(&GameEvent::PieceHeld(first)));
+        assert_eq!(game.held, Some(first));
+        let second = game.active.as_ref().unwrap().letter;
+
+        let events = game.tick(Duration::ZERO, &[Input::Hold]);
+        assert!(!events.iter().any(|event| matches!(event, GameEvent::PieceHeld(_))));
+        assert_eq!(game.active.as_ref().unwrap().letter, second);
+
+        // Locking a piece allows another hold, which swaps the held piece back in
+        game.tick(Duration::ZERO, &[Input::HardDrop]);
+        assert!(game.can_hold);
+        let third = game.active.as_ref().unwrap().letter;
+        game.tick(Duration::ZERO, &[Input::Hold]);
+        assert_eq!(game.active.as_ref().unwrap().letter, first);
+        assert_eq!(game.held, Some(third));
+    }
+
+    #[test]
+    fn extended_placement_runs_out_of_lock_resets() {
+        let mut game = started_game(GameSettings::default());
+        set_active(&mut game, TetrominoLetter::T);
+        game.tick(Duration::ZERO, &[Input::SonicDrop]);
+
+        // Every move on the ground resets the lock delay until the resets are used up
+        let wiggle = |tick: usize| if tick.is_multiple_of(2) { Input::Shift(1) } else { Input::Shift(-1) };
+        // The first move only starts the timer, each one after that uses a reset
+        for tick in 0..MAX_LOCK_RESETS {
+            game.tick(FRAME, &[wiggle(tick)]);
+            assert_eq!(game.stats.pieces_placed, 0, "locked on tick {}", tick);
+        }
+        assert_eq!(game.lock_down.resets_used, MAX_LOCK_RESETS - 1);
+
+        // Using the last one leaves the piece nothing to wait for
+        game.tick(FRAME, &[wiggle(MAX_LOCK_RESETS)]);
+        assert_eq!(game.stats.pieces_placed, 1);
+    }
+
+    #[test]
+    fn infinite_lock_down_never_runs_out() {
+        let settings = GameSettings { lock_down_mode: LockDownMode::Infinite, ..GameSettings::default() };
+        let mut game = started_game(settings);
+        set_active(&mut game, TetrominoLetter::T);
+        game.tick(Duration::ZERO, &[Input::SonicDrop]);
+
+        for tick in 0..100usize {
+            game.tick(FRAME, &[if tick.is_multiple_of(2) { Input::Shift(1) } else { Input::Shift(-1) }]);
+        }
+        assert_eq!(game.stats.pieces_placed, 0);
+    }
+
+    #[test]
+    fn locking_above_the_visible_board_tops_out() {
+        let mut game = started_game(GameSettings::default());
+        set_active(&mut game, TetrominoLetter::T);
+        for y in 0..BOARD_HEIGHT as i32 {
+            for x in 1..BOARD_WIDTH as i32 {
+                fill(&mut game, x, y);
+            }
+        }
+
+        let events = game.tick(Duration::ZERO, &[Input::HardDrop]);
+        assert!(events.contains(&GameEvent::ToppedOut));
+        assert!(game.is_over);
+        assert!(game.active.is_none());
+        assert!(game.tick(FRAME, &[Input::HardDrop]).is_empty());
+    }
+
+    #[test]
+    fn inputs_after_a_lock_go_to_the_next_piece() {
+        let mut game = started_game(GameSettings::default());
+        let next = game.queue[0];
+
+        game.tick(Duration::ZERO, &[Input::HardDrop, Input::Hold]);
+        assert_eq!(game.stats.pieces_placed, 1);
+        assert_eq!(game.held, None);
+
+        let events = game.tick(Duration::ZERO, &[]);
+        assert!(events.contains(&GameEvent::PieceHeld(next)));
+        assert_eq!(game.held, Some(next));
+
+        // Two hard drops in one frame place two pieces
+        game.tick(Duration::ZERO, &[Input::HardDrop, Input::HardDrop]);
+        game.tick(Duration::ZERO, &[]);
+        assert_eq!(game.stats.pieces_placed, 3);
+    }
+
+    #[test]
+    fn lock_delay_only_shrinks_past_twenty_g() {
+        for level in 1..=30 {
+            assert_eq!(GravityCurve::Classic.lock_delay_seconds(level), 0.5);
+        }
+        assert_eq!(GravityCurve::TwentyG.lock_delay_seconds(1), 0.5);
+        assert!((GravityCurve::TwentyG.lock_delay_seconds(3) - 0.4).abs() < 1e-6);
+        assert_eq!(GravityCurve::TwentyG.lock_delay_seconds(30), 0.25);
+
+        let first_twenty_g = (1..=30).find(|&level| GravityCurve::Guideline.rows_per_frame(level) >= TWENTY_G).unwrap();
+        assert_eq!(GravityCurve::Guideline.lock_delay_seconds(first_twenty_g - 1), 0.5);
+        assert_eq!(GravityCurve::Guideline.lock_delay_seconds(first_twenty_g), 0.5);
+        assert!(GravityCurve::Guideline.lock_delay_seconds(first_twenty_g + 1) < 0.5);
+    }
+
+    #[test]
+    fn perfect_clear_single_with_a_hard_drop() {
+        let mut game = started_game(GameSettings::default());
+        set_active(&mut game, TetrominoLetter::I);
+        for x in (0..3).chain(7..BOARD_WIDTH as i32) {
+            fill(&mut game, x, 0);
+        }
+
+        // 20 rows of hard drop, a level 1 single and its perfect clear bonus
+        game.tick(Duration::ZERO, &[Input::HardDrop]);
+        assert_eq!(game.scoring.score, 2 * 20 + 100 + 800);
+        assert!(game.board.cells.iter().all(|cell| *cell == Cell::Empty));
+    }
+
+    #[test]
+    fn gravity_curves() {
+        let rows_per_second = |curve: GravityCurve, level: usize| curve.rows_per_frame(level) * FRAMES_PER_SECOND;
+        assert!((rows_per_second(GravityCurve::Guideline, 1) - 1.0).abs() < 1e-4);
+        assert!((rows_per_second(GravityCurve::Guideline, 2) - 1.0 / 0.793).abs() < 1e-3);
+        assert!((rows_per_second(GravityCurve::Guideline, 10) - 1.0 / 0.06415).abs() < 0.01);
+        assert!(GravityCurve::Guideline.rows_per_frame(18) < TWENTY_G);
+        assert_eq!(GravityCurve::Guideline.rows_per_frame(19), TWENTY_G);
+
+        assert!((rows_per_second(GravityCurve::Classic, 1) - 1.0).abs() < 1e-4);
+        assert!((rows_per_second(GravityCurve::Classic, 10) - 20.0).abs() < 1e-3);
+        // The classic curve tops out at a row a frame and never reaches 20G
+        assert!((GravityCurve::Classic.rows_per_frame(30) - 1.0).abs() < 1e-3);
+
+        for level in [1, 15, 30] {
+            assert_eq!(GravityCurve::TwentyG.rows_per_frame(level), TWENTY_G);
+        }
+        // Every curve only gets faster
+        for curve in [GravityCurve::Guideline, GravityCurve::Classic, GravityCurve::TwentyG] {
+            for level in 1..30 {
+                assert!(curve.rows_per_frame(level + 1) >= curve.rows_per_frame(level), "{:?} level {}", curve, level);
+            }
+        }
+    }
+
+    #[test]
+    fn guideline_lock_delay_shrinks_from_level_twenty() {
+        for level in 1..=19 {
+            assert_eq!(GravityCurve::Guideline.lock_delay_seconds(level), 0.5, "level {}", level);
+        }
+        assert!((GravityCurve::Guideline.lock_delay_seconds(20) - 0.45).abs() < 1e-6);
+        assert!((GravityCurve::Guideline.lock_delay_seconds(22) - 0.35).abs() < 1e-6);
+        assert_eq!(GravityCurve::Guideline.lock_delay_seconds(24), 0.25);
+        assert_eq!(GravityCurve::Guideline.lock_delay_seconds(40), 0.25);
+
+        // A new game and a level up both pick it up from the curve
+        let game = started_game(GameSettings { start_level: 20, ..GameSettings::default() });
+        assert_eq!(game.lock_down.delay, Duration::from_secs_f32(0.45));
+        let game = started_game(GameSettings { start_level: 20, gravity_curve: GravityCurve::Classic, ..GameSettings::default() });
+        assert_eq!(game.lock_down.delay, Duration::from_secs_f32(0.5));
+    }
+
+    #[test]
+    fn queue_stays_longer_than_the_preview() {
+        for preview_count in [0, 1, 6, 12] {
+            let mut game = started_game(GameSettings { preview_count, ..GameSettings::default() });
+            for _ in 0..30 {
+                assert!(game.queue.len() > preview_count, "preview {}", preview_count);
+                game.tick(Duration::ZERO, &[Input::HardDrop]);
+                if game.is_over {
+                    break;
+                }
+            }
+        }
+
+        // The preview length doesn't change which pieces a seed deals
+        let mut short = started_game(GameSettings { preview_count: 1, seed: 7, ..GameSettings::default() });
+        let mut long = started_game(GameSettings { preview_count: 6, seed: 7, ..GameSettings::default() });
+        for _ in 0..20 {
+            assert_eq!(short.active.as_ref().map(|piece| piece.letter), long.active.as_ref().map(|piece| piece.letter));
+            short.tick(Duration::ZERO, &[Input::Hold]);
+            long.tick(Duration::ZERO, &[Input::Hold]);
+            short.tick(Duration::ZERO, &[Input::Shift(-10), Input::HardDrop]);
+            long.tick(Duration::ZERO, &[Input::Shift(-10), Input::HardDrop]);
+        }
+    }
+
+    #[test]
+    fn step_reset_restarts_the_lock_delay_on_every_fall() {
+        for (mode, elapsed_after_fall) in [(LockDownMode::StepReset, FRAME), (LockDownMode::ExtendedPlacement, FRAME * 3)] {
+            let mut game = started_game(GameSettings { lock_down_mode: mode, gravity_curve: GravityCurve::TwentyG, ..GameSettings::default() });
+            game.tick(FRAME, &[]);
+            game.tick(FRAME, &[]);
+            assert_eq!(game.lock_down.elapsed, FRAME * 2);
+
+            // Lifted a row like an upward kick would, the piece falls straight back onto a row it already reached
+            game.active.as_mut().unwrap().position.1 += 1;
+            game.tick(FRAME, &[]);
+            assert!(game.active.as_ref().unwrap().is_grounded(&game.board));
+            assert_eq!(game.lock_down.elapsed, elapsed_after_fall, "{:?}", mode);
+        }
+    }
+}
\ No newline at end of file
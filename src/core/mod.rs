@@ -0,0 +1,15 @@
+// The rules of the game with no Bevy in them. A Game takes inputs and a tick and reports what happened
+// as GameEvents, the Bevy plugins feed it the keyboard and draw whatever it says changed.
+// Bots, replays or a server can drive a Game the same way.
+pub mod board;
+pub mod piece;
+pub mod rotation;
+pub mod scoring;
+pub mod randomizer;
+pub mod game;
+
+pub use board::{Cell, BOARD_WIDTH, BOARD_HEIGHT, BOARD_HIDDEN_HEIGHT, BOARD_TOTAL_HEIGHT};
+pub use piece::{Piece, TetrominoLetter};
+pub use scoring::{TSpin, MAX_START_LEVEL};
+pub use randomizer::RandomizerKind;
+pub use game::{Game, GameSettings, GameEvent, Input, ModeGoal};
\ No newline at end of file
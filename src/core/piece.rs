@@ -0,0 +1,95 @@
+use crate::core::board::{Board, BOARD_HEIGHT};
+use crate::core::rotation::{RotationKick, RotationSystem};
+
+#[derive(Clone, Debug, PartialEq, Eq, Copy, Hash)]
+pub enum TetrominoLetter {
+    I,
+    J,
+    L,
+    O,
+    S,
+    Z,
+    T,
+}
+impl TetrominoLetter {
+    pub const ALL: [TetrominoLetter; 7] = [
+        TetrominoLetter::I,
+        TetrominoLetter::O,
+        TetrominoLetter::T,
+        TetrominoLetter::S,
+        TetrominoLetter::Z,
+        TetrominoLetter::J,
+        TetrominoLetter::L,
+    ];
+}
+
+pub type Shape = [[bool; 4]; 4]; // shape[y][x], y goes down the 4x4 box
+
+pub const SPAWN_POSITION: (i32, i32) = (3, 21);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Piece {
+    pub shape: Shape,
+    pub position: (i32, i32), // Board position of the top left of the 4x4 shape
+    pub rotation: usize, // 0-3 for 0-270 degrees
+    pub letter: TetrominoLetter,
+    pub last_rotation_kick: Option<RotationKick>, // How the last rotation turned and kicked, None if the last action wasn't a rotation
+}
+
+impl Piece {
+    // A piece in its spawn orientation, where the rotation system puts it
+    pub fn new(letter: TetrominoLetter, rotation_system: &dyn RotationSystem) -> Self {
+        Piece {
+            shape: rotation_system.shape(letter, 0),
+            position: rotation_system.spawn_position(letter),
+            rotation: 0,
+            letter,
+            last_rotation_kick: None
+        }
+    }
+
+    // Board coordinates of every cell in the piece
+    pub fn cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        shape_cells(&self.shape, self.position)
+    }
+
+    pub fn lowest_row(&self) -> i32 {
+        self.cells().map(|(_, y)| y).min().unwrap_or(i32::MAX)
+    }
+
+    pub fn collides(&self, board: &Board) -> bool {
+        is_collision(board, self.position, &self.shape)
+    }
+
+    pub fn can_move(&self, board: &Board, dx: i32, dy: i32) -> bool {
+        !is_collision(board, (self.position.0 + dx, self.position.1 + dy), &self.shape)
+    }
+
+    // Resting on the floor or on locked in cells
+    pub fn is_grounded(&self, board: &Board) -> bool {
+        !self.can_move(board, 0, -1)
+    }
+
+    pub fn drop_distance(&self, board: &Board) -> usize {
+        let mut distance = 0;
+        while self.can_move(board, 0, -(distance as i32 + 1)) {
+            distance += 1;
+        }
+        distance
+    }
+
+    // Locking with any part of the piece above the visible board ends the game
+    pub fn is_locked_out(&self) -> bool {
+        self.cells().any(|(_, y)| y >= BOARD_HEIGHT as i32)
+    }
+}
+
+pub fn shape_cells(shape: &Shape, position: (i32, i32)) -> impl Iterator<Item = (i32, i32)> + '_ {
+    (0..4).flat_map(move |y| (0..4).map(move |x| (x, y)))
+        .filter(|(x, y)| shape[*y][*x])
+        .map(move |(x, y)| (position.0 + x as i32, position.1 - y as i32))
+}
+
+pub fn is_collision(board: &Board, position: (i32, i32), shape: &Shape) -> bool {
+    shape_cells(shape, position).any(|(x, y)| board.is_blocked(x, y))
+}
\ No newline at end of file
@@ -0,0 +1,302 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use crate::core::piece::TetrominoLetter;
+
+// Deals the piece sequence. Every randomizer draws from the game's seeded RNG so a seed always replays the same pieces
+pub trait Randomizer: Debug + Send + Sync {
+    fn next(&mut self, rng: &mut StdRng) -> TetrominoLetter;
+    fn box_clone(&self) -> Box<dyn Randomizer>;
+}
+impl Clone for Box<dyn Randomizer> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+// Which randomizer a game is dealt with
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RandomizerKind {
+    SevenBag,
+    FourteenBag,
+    TgmHistory { rolls: usize },
+    Nes,
+    Random,
+}
+impl RandomizerKind {
+    pub const ALL: [RandomizerKind; 6] = [
+        RandomizerKind::SevenBag,
+        RandomizerKind::FourteenBag,
+        RandomizerKind::TgmHistory { rolls: 4 },
+        RandomizerKind::TgmHistory { rolls: 6 },
+        RandomizerKind::Nes,
+        RandomizerKind::Random,
+    ];
+
+    pub fn next(&self) -> Self {
+        let index = RandomizerKind::ALL.iter().position(|kind| kind == self).unwrap_or(0);
+        RandomizerKind::ALL[(index + 1) % RandomizerKind::ALL.len()]
+    }
+
+    pub fn previous(&self) -> Self {
+        let index = RandomizerKind::ALL.iter().position(|kind| kind == self).unwrap_or(0);
+        RandomizerKind::ALL[(index + RandomizerKind::ALL.len() - 1) % RandomizerKind::ALL.len()]
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            RandomizerKind::SevenBag => "7-Bag".to_string(),
+            RandomizerKind::FourteenBag => "14-Bag".to_string(),
+            RandomizerKind::TgmHistory { rolls } => format!("TGM History ({} rolls)", rolls),
+            RandomizerKind::Nes => "NES".to_string(),
+            RandomizerKind::Random => "Random".to_string(),
+        }
+    }
+
+    pub fn create(&self) -> Box<dyn Randomizer> {
+        match self {
+            RandomizerKind::SevenBag => Box::new(Bag::new(1)),
+            RandomizerKind::FourteenBag => Box::new(Bag::new(2)),
+            RandomizerKind::TgmHistory { rolls } => Box::new(TgmHistory::new(*rolls)),
+            RandomizerKind::Nes => Box::new(Nes { last: None }),
+            RandomizerKind::Random => Box::new(PureRandom),
+        }
+    }
+}
+
+// Every piece `copies` times in a shuffled bag, a new bag once it runs out.
+// One copy never goes more than 12 pieces without any given piece, two copies allow streaks and longer droughts
+#[derive(Clone, Debug)]
+pub struct Bag {
+    pub copies: usize,
+    pub bag: Vec<TetrominoLetter>,
+}
+impl Bag {
+    pub fn new(copies: usize) -> Self {
+        Bag { copies, bag: vec![] }
+    }
+}
+impl Randomizer for Bag {
+    fn next(&mut self, rng: &mut StdRng) -> TetrominoLetter {
+        if self.bag.is_empty() {
+            for _ in 0..self.copies {
+                self.bag.extend(TetrominoLetter::ALL);
+            }
+            self.bag.shuffle(rng);
+        }
+        self.bag.pop().expect("the bag was just refilled")
+    }
+
+    fn box_clone(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+// Remembers the last 4 pieces and rerolls up to `rolls` times to avoid them, like The Grand Master.
+// The history starts full of S and Z and the first piece is never S, Z or O, so games never open on an overhang
+#[derive(Clone, Debug)]
+pub struct TgmHistory {
+    pub rolls: usize,
+    pub history: VecDeque<TetrominoLetter>,
+    pub is_first: bool,
+}
+impl TgmHistory {
+    pub fn new(rolls: usize) -> Self {
+        let history = VecDeque::from([TetrominoLetter::Z, TetrominoLetter::S, TetrominoLetter::S, TetrominoLetter::Z]);
+        TgmHistory { rolls, history, is_first: true }
+    }
+}
+impl Randomizer for TgmHistory {
+    fn next(&mut self, rng: &mut StdRng) -> TetrominoLetter {
+        let letter = if self.is_first {
+            self.is_first = false;
+            *[TetrominoLetter::I, TetrominoLetter::J, TetrominoLetter::L, TetrominoLetter::T].choose(rng).expect("not empty")
+        } else {
+            // The last roll is kept even if it's in the history
+            let mut letter = random_letter(rng);
+            for _ in 1..self.rolls {
+                if !self.history.contains(&letter) {
+                    break;
+                }
+                letter = random_letter(rng);
+            }
+            letter
+        };
+        self.history.pop_front();
+        self.history.push_back(letter);
+        letter
+    }
+
+    fn box_clone(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+// Rolls 8 sides where the 8th means roll again, and also rolls again on a repeat of the last piece.
+// The second roll is kept whatever it is, so repeats are rare but droughts are unbounded
+#[derive(Clone, Debug)]
+pub struct Nes {
+    pub last: Option<TetrominoLetter>,
+}
+impl Randomizer for Nes {
+    fn next(&mut self, rng: &mut StdRng) -> TetrominoLetter {
+        let roll = rng.gen_range(0..TetrominoLetter::ALL.len() + 1);
+        let letter = match TetrominoLetter::ALL.get(roll) {
+            Some(letter) if Some(*letter) != self.last => *letter,
+            _ => random_letter(rng),
+        };
+        self.last = Some(letter);
+        letter
+    }
+
+    fn box_clone(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+// Every piece equally likely every time, no protection against floods or droughts
+#[derive(Clone, Debug)]
+pub struct PureRandom;
+impl Randomizer for PureRandom {
+    fn next(&mut self, rng: &mut StdRng) -> TetrominoLetter {
+        random_letter(rng)
+    }
+
+    fn box_clone(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+fn random_letter(rng: &mut StdRng) -> TetrominoLetter {
+    *TetrominoLetter::ALL.choose(rng).expect("not empty")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    const DEALT: usize = 70_000;
+
+    fn deal(randomizer: &mut dyn Randomizer, seed: u64, count: usize) -> Vec<TetrominoLetter> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count).map(|_| randomizer.next(&mut rng)).collect()
+    }
+
+    fn count(pieces: &[TetrominoLetter], letter: TetrominoLetter) -> usize {
+        pieces.iter().filter(|piece| **piece == letter).count()
+    }
+
+    // Most other pieces dealt in a row between two of this one
+    fn longest_drought(pieces: &[TetrominoLetter], letter: TetrominoLetter) -> usize {
+        let positions: Vec<usize> = pieces.iter().enumerate().filter(|(_, piece)| **piece == letter).map(|(index, _)| index).collect();
+        positions.windows(2).map(|pair| pair[1] - pair[0] - 1).max().unwrap()
+    }
+
+    // Share of the gaps between two of the same piece that are longer than `pieces_between`
+    fn long_drought_share(pieces: &[TetrominoLetter], pieces_between: usize) -> f64 {
+        let (mut gaps, mut long_gaps) = (0, 0);
+        for letter in TetrominoLetter::ALL {
+            let positions: Vec<usize> = pieces.iter().enumerate().filter(|(_, piece)| **piece == letter).map(|(index, _)| index).collect();
+            for pair in positions.windows(2) {
+                gaps += 1;
+                if pair[1] - pair[0] - 1 > pieces_between {
+                    long_gaps += 1;
+                }
+            }
+        }
+        long_gaps as f64 / gaps as f64
+    }
+
+    fn repeat_rate(pieces: &[TetrominoLetter]) -> f64 {
+        let repeats = pieces.windows(2).filter(|pair| pair[0] == pair[1]).count();
+        repeats as f64 / (pieces.len() - 1) as f64
+    }
+
+    #[test]
+    fn seven_bag_deals_every_piece_each_bag() {
+        let pieces = deal(&mut Bag::new(1), 1, DEALT);
+        for bag in pieces.chunks(7) {
+            for letter in TetrominoLetter::ALL {
+                assert_eq!(count(bag, letter), 1, "{:?} in {:?}", letter, bag);
+            }
+        }
+
+        // At most 12 other pieces between two copies of the same piece
+        for letter in TetrominoLetter::ALL {
+            let longest_gap = longest_drought(&pieces, letter);
+            assert!(longest_gap <= 12, "{:?} went {} pieces without showing up", letter, longest_gap);
+        }
+    }
+
+    #[test]
+    fn fourteen_bag_deals_every_piece_twice_each_bag() {
+        let pieces = deal(&mut Bag::new(2), 2, DEALT);
+        for bag in pieces.chunks(14) {
+            for letter in TetrominoLetter::ALL {
+                assert_eq!(count(bag, letter), 2, "{:?} in {:?}", letter, bag);
+            }
+        }
+
+        // Droughts run longer than a 7-bag's, but both copies at the front of one bag and the back
+        // of the next is still only 24 pieces
+        let longest_gaps: Vec<usize> = TetrominoLetter::ALL.iter().map(|letter| longest_drought(&pieces, *letter)).collect();
+        assert!(longest_gaps.iter().all(|gap| *gap <= 24), "{:?}", longest_gaps);
+        assert!(longest_gaps.iter().any(|gap| *gap > 12), "{:?}", longest_gaps);
+    }
+
+    #[test]
+    fn tgm_history_never_opens_on_an_overhang_and_rarely_repeats() {
+        for seed in 0..1000 {
+            let first = deal(&mut TgmHistory::new(4), seed, 1)[0];
+            assert!(![TetrominoLetter::S, TetrominoLetter::Z, TetrominoLetter::O].contains(&first), "seed {} opened on {:?}", seed, first);
+        }
+
+        // Uniform random repeats 1 in 7 times
+        for rolls in [4, 6] {
+            let pieces = deal(&mut TgmHistory::new(rolls), 3, DEALT);
+            let rate = repeat_rate(&pieces);
+            assert!(rate < 1.0 / 7.0 / 4.0, "{} rolls repeated {:.3} of the time", rolls, rate);
+
+            // Pieces that haven't shown up in a while are the ones rerolls land on, so droughts stay short.
+            // Uniform random has about 4% of its gaps over 20 pieces and goes 60 without a piece
+            let share = long_drought_share(&pieces, 20);
+            assert!(share < 0.01, "{} rolls went over 20 pieces without a piece {:.4} of the time", rolls, share);
+            for letter in TetrominoLetter::ALL {
+                let longest_gap = longest_drought(&pieces, letter);
+                assert!(longest_gap <= 40, "{} rolls went {} pieces without {:?}", rolls, longest_gap, letter);
+            }
+        }
+    }
+
+    #[test]
+    fn nes_repeats_less_than_uniform() {
+        // The reroll brings repeats down to about 1 in 28
+        let pieces = deal(&mut Nes { last: None }, 4, DEALT);
+        let rate = repeat_rate(&pieces);
+        assert!(rate < 1.0 / 7.0 / 2.0, "repeated {:.3} of the time", rate);
+
+        // Only repeats are rerolled, so droughts longer than any bag allows still happen
+        assert!(TetrominoLetter::ALL.iter().all(|letter| longest_drought(&pieces, *letter) > 24));
+        assert!(long_drought_share(&pieces, 20) > 0.02);
+    }
+
+    #[test]
+    fn pure_random_deals_each_piece_a_seventh_of_the_time() {
+        let pieces = deal(&mut PureRandom, 5, DEALT);
+        for letter in TetrominoLetter::ALL {
+            let frequency = count(&pieces, letter) as f64 / DEALT as f64;
+            assert!((frequency - 1.0 / 7.0).abs() < 0.01, "{:?} dealt {:.4} of the time", letter, frequency);
+        }
+        // Nothing stops repeats, so they stay near 1 in 7
+        assert!((repeat_rate(&pieces) - 1.0 / 7.0).abs() < 0.01);
+
+        // Or droughts: a gap over 20 pieces has a (6/7)^21 chance
+        assert!(TetrominoLetter::ALL.iter().all(|letter| longest_drought(&pieces, *letter) > 24));
+        let share = long_drought_share(&pieces, 20);
+        assert!((share - (6.0f64 / 7.0).powi(21)).abs() < 0.005, "{:.4} of gaps over 20 pieces", share);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,701 @@
+use std::fmt::Debug;
+
+use crate::core::board::{Board, BOARD_WIDTH, BOARD_TOTAL_HEIGHT};
+use crate::core::piece::{Piece, Shape, TetrominoLetter, SPAWN_POSITION, is_collision};
+use crate::core::scoring::TSpin;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RotationDirection {
+    Clockwise,
+    CounterClockwise,
+    Half,
+}
+impl RotationDirection {
+    pub fn target_rotation(&self, rotation: usize) -> usize {
+        match self {
+            RotationDirection::Clockwise => (rotation + 1) % 4,
+            RotationDirection::CounterClockwise => (rotation + 3) % 4,
+            RotationDirection::Half => (rotation + 2) % 4,
+        }
+    }
+}
+
+// How a piece's last rotation got it where it is
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RotationKick {
+    pub direction: RotationDirection,
+    pub index: usize, // Index into the kick table, 0 if it turned in place
+}
+
+// Owns how pieces turn: the shape of every rotation state, where pieces spawn and which kicks a rotation tries
+pub trait RotationSystem: Debug + Send + Sync {
+    fn shape(&self, letter: TetrominoLetter, rotation: usize) -> Shape;
+    // Offsets to try when the rotated piece doesn't fit where it is, in the same order and format as get_kick_table_scenario
+    fn kicks(&self, letter: TetrominoLetter, from: usize, to: usize) -> Vec<(i32, i32)>;
+    fn spawn_position(&self, _letter: TetrominoLetter) -> (i32, i32) {
+        SPAWN_POSITION
+    }
+    fn box_clone(&self) -> Box<dyn RotationSystem>;
+}
+impl Clone for Box<dyn RotationSystem> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+// Which rotation system a game is played with
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RotationSystemKind {
+    Srs,
+    SrsPlus,
+    Ars,
+    Nrs,
+    NoKick,
+}
+impl RotationSystemKind {
+    pub const ALL: [RotationSystemKind; 5] = [
+        RotationSystemKind::Srs,
+        RotationSystemKind::SrsPlus,
+        RotationSystemKind::Ars,
+        RotationSystemKind::Nrs,
+        RotationSystemKind::NoKick,
+    ];
+
+    pub fn next(&self) -> Self {
+        let index = RotationSystemKind::ALL.iter().position(|kind| kind == self).unwrap_or(0);
+        RotationSystemKind::ALL[(index + 1) % RotationSystemKind::ALL.len()]
+    }
+
+    pub fn previous(&self) -> Self {
+        let index = RotationSystemKind::ALL.iter().position(|kind| kind == self).unwrap_or(0);
+        RotationSystemKind::ALL[(index + RotationSystemKind::ALL.len() - 1) % RotationSystemKind::ALL.len()]
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            RotationSystemKind::Srs => "SRS",
+            RotationSystemKind::SrsPlus => "SRS+",
+            RotationSystemKind::Ars => "ARS",
+            RotationSystemKind::Nrs => "NRS",
+            RotationSystemKind::NoKick => "No Kicks",
+        }
+    }
+
+    pub fn create(&self) -> Box<dyn RotationSystem> {
+        match self {
+            RotationSystemKind::Srs => Box::new(Srs),
+            RotationSystemKind::SrsPlus => Box::new(SrsPlus),
+            RotationSystemKind::Ars => Box::new(Ars),
+            RotationSystemKind::Nrs => Box::new(Nrs),
+            RotationSystemKind::NoKick => Box::new(NoKick),
+        }
+    }
+}
+
+// Builds a shape from (x, y) cells in the 4x4 box
+fn shape_from_cells(cells: [(usize, usize); 4]) -> Shape {
+    let mut shape = [[false; 4]; 4];
+    for (x, y) in cells {
+        shape[y][x] = true;
+    }
+    shape
+}
+
+// Guideline spawn states, flat side down in the top two rows of the box. JLSTZ turn around the center
+// of their 3x3 box and I around the center of the 4x4, which are the states the SRS kick tables are written for
+fn srs_shape(letter: TetrominoLetter, rotation: usize) -> Shape {
+    let (cells, pivot) = match letter {
+        TetrominoLetter::I => ([(0, 1), (1, 1), (2, 1), (3, 1)], (3, 3)),
+        TetrominoLetter::J => ([(0, 0), (0, 1), (1, 1), (2, 1)], (2, 2)),
+        TetrominoLetter::L => ([(2, 0), (0, 1), (1, 1), (2, 1)], (2, 2)),
+        TetrominoLetter::O => return shape_from_cells([(1, 0), (2, 0), (1, 1), (2, 1)]),
+        TetrominoLetter::S => ([(1, 0), (2, 0), (0, 1), (1, 1)], (2, 2)),
+        TetrominoLetter::Z => ([(0, 0), (1, 0), (1, 1), (2, 1)], (2, 2)),
+        TetrominoLetter::T => ([(1, 0), (0, 1), (1, 1), (2, 1)], (2, 2)),
+    };
+
+    // Offsets from the pivot are doubled so the I piece's pivot between cells stays a whole number
+    let rotated = cells.map(|(x, y): (i32, i32)| {
+        let (mut dx, mut dy) = (x * 2 - pivot.0, y * 2 - pivot.1);
+        for _ in 0..rotation % 4 {
+            (dx, dy) = (-dy, dx);
+        }
+        (((dx + pivot.0) / 2) as usize, ((dy + pivot.1) / 2) as usize)
+    });
+    shape_from_cells(rotated)
+}
+
+// SRS
+pub fn get_kick_table_scenario(
+    letter: &TetrominoLetter,
+    from: &usize,
+    to: &usize
+) -> Vec<(i32, i32)> {
+    // Written exactly as the SRS reference tables, +x is right and +y is up like the board's rows
+    match letter {
+        TetrominoLetter::J | TetrominoLetter::L | TetrominoLetter::S | TetrominoLetter::T | TetrominoLetter::Z => {
+            // JLSTZ pieces
+            match (from, to) {
+                (0, 1) => vec![(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+                (1, 2) => vec![(0,0), (1,0), (1,-1), (0,2), (1,2)],
+                (2, 3) => vec![(0,0), (1,0), (1,1), (0,-2), (1,-2)],
+                (3, 0) => vec![(0,0), (-1,0), (-1,-1), (0,2), (-1,2)],
+                (1, 0) => vec![(0,0), (1,0), (1,-1), (0,2), (1,2)],
+                (2, 1) => vec![(0,0), (-1,0), (-1,1), (0,-2), (-1,-2)],
+                (3, 2) => vec![(0,0), (-1,0), (-1,-1), (0,2), (-1,2)],
+                (0, 3) => vec![(0,0), (1,0), (1,1), (0,-2), (1,-2)],
+                _ => vec![],
+            }
+        }
+        TetrominoLetter::I => {
+            // I piece
+            match (from, to) {
+                (0, 1) => vec![(0,0), (-2,0), (1,0), (-2,-1), (1,2)],
+                (1, 2) => vec![(0,0), (-1,0), (2,0), (-1,2), (2,-1)],
+                (2, 3) => vec![(0,0), (2,0), (-1,0), (2,1), (-1,-2)],
+                (3, 0) => vec![(0,0), (1,0), (-2,0), (1,-2), (-2,1)],
+                (1, 0) => vec![(0,0), (2,0), (-1,0), (2,1), (-1,-2)],
+                (2, 1) => vec![(0,0), (1,0), (-2,0), (1,-2), (-2,1)],
+                (3, 2) => vec![(0,0), (-2,0), (1,0), (-2,-1), (1,2)],
+                (0, 3) => vec![(0,0), (-1,0), (2,0), (-1,2), (2,-1)],
+                _ => vec![],
+            }
+        }
+        TetrominoLetter::O => {
+            // O piece
+            // No kick table needed
+            vec![]
+        }
+    }
+}
+
+// SRS+: SRS with I kicks that mirror each other left and right
+fn srs_plus_kicks(letter: TetrominoLetter, from: usize, to: usize) -> Vec<(i32, i32)> {
+    match (letter, from, to) {
+        (TetrominoLetter::O, _, _) => vec![],
+        (TetrominoLetter::I, 0, 1) => vec![(0,0), (1,0), (-2,0), (-2,-1), (1,2)],
+        (TetrominoLetter::I, 1, 0) => vec![(0,0), (-1,0), (2,0), (-1,-2), (2,1)],
+        (TetrominoLetter::I, 1, 2) => vec![(0,0), (-1,0), (2,0), (-1,2), (2,-1)],
+        (TetrominoLetter::I, 2, 1) => vec![(0,0), (-2,0), (1,0), (-2,1), (1,-2)],
+        (TetrominoLetter::I, 2, 3) => vec![(0,0), (2,0), (-1,0), (2,1), (-1,-2)],
+        (TetrominoLetter::I, 3, 2) => vec![(0,0), (1,0), (-2,0), (1,-2), (-2,1)],
+        (TetrominoLetter::I, 3, 0) => vec![(0,0), (1,0), (-2,0), (1,-2), (-2,1)],
+        (TetrominoLetter::I, 0, 3) => vec![(0,0), (-1,0), (2,0), (2,1), (-1,-2)],
+        _ => get_kick_table_scenario(&letter, &from, &to),
+    }
+}
+
+// 180 rotations, from TETR.IO's SRS+. The SRS guideline has none, so there a half turn only works in place
+pub fn get_180_kick_table(letter: TetrominoLetter, from: usize, to: usize) -> Vec<(i32, i32)> {
+    match (letter, from, to) {
+        (TetrominoLetter::O, _, _) => vec![],
+        (_, 0, 2) => vec![(0,0), (0,1), (1,1), (-1,1), (1,0), (-1,0)],
+        (_, 2, 0) => vec![(0,0), (0,-1), (-1,-1), (1,-1), (-1,0), (1,0)],
+        (_, 1, 3) => vec![(0,0), (1,0), (1,2), (1,1), (0,2), (0,1)],
+        (_, 3, 1) => vec![(0,0), (-1,0), (-1,2), (-1,1), (0,2), (0,1)],
+        _ => vec![],
+    }
+}
+
+fn is_half_turn(from: usize, to: usize) -> bool {
+    (from + 2) % 4 == to
+}
+
+// ARS, from The Grand Master. J, L and T spawn pointing down and every state sits on the bottom of a 3x3 box,
+// so pieces don't jump up when they turn. S, Z and I only have two states
+fn ars_shape(letter: TetrominoLetter, rotation: usize) -> Shape {
+    let cells = match (letter, rotation % 4) {
+        (TetrominoLetter::I, 0 | 2) => [(0, 1), (1, 1), (2, 1), (3, 1)],
+        (TetrominoLetter::I, _) => [(2, 0), (2, 1), (2, 2), (2, 3)],
+        (TetrominoLetter::O, _) => [(1, 1), (2, 1), (1, 2), (2, 2)],
+        (TetrominoLetter::S, 0 | 2) => [(1, 1), (2, 1), (0, 2), (1, 2)],
+        (TetrominoLetter::S, _) => [(0, 0), (0, 1), (1, 1), (1, 2)],
+        (TetrominoLetter::Z, 0 | 2) => [(0, 1), (1, 1), (1, 2), (2, 2)],
+        (TetrominoLetter::Z, _) => [(2, 0), (2, 1), (1, 1), (1, 2)],
+        (TetrominoLetter::T, 0) => [(0, 1), (1, 1), (2, 1), (1, 2)],
+        (TetrominoLetter::T, 1) => [(1, 0), (0, 1), (1, 1), (1, 2)],
+        (TetrominoLetter::T, 2) => [(1, 1), (0, 2), (1, 2), (2, 2)],
+        (TetrominoLetter::T, _) => [(1, 0), (1, 1), (2, 1), (1, 2)],
+        (TetrominoLetter::J, 0) => [(0, 1), (1, 1), (2, 1), (2, 2)],
+        (TetrominoLetter::J, 1) => [(1, 0), (1, 1), (0, 2), (1, 2)],
+        (TetrominoLetter::J, 2) => [(0, 1), (0, 2), (1, 2), (2, 2)],
+        (TetrominoLetter::J, _) => [(1, 0), (2, 0), (1, 1), (1, 2)],
+        (TetrominoLetter::L, 0) => [(0, 1), (1, 1), (2, 1), (0, 2)],
+        (TetrominoLetter::L, 1) => [(0, 0), (1, 0), (1, 1), (1, 2)],
+        (TetrominoLetter::L, 2) => [(2, 1), (0, 2), (1, 2), (2, 2)],
+        (TetrominoLetter::L, _) => [(1, 0), (1, 1), (1, 2), (2, 2)],
+    };
+    shape_from_cells(cells)
+}
+
+// NRS, from the NES game. Same spawn states as ARS but every piece turns around a fixed center,
+// with S and Z held on the right of it
+fn nrs_shape(letter: TetrominoLetter, rotation: usize) -> Shape {
+    let cells = match (letter, rotation % 4) {
+        (TetrominoLetter::S, 1 | 3) => [(1, 0), (1, 1), (2, 1), (2, 2)],
+        (TetrominoLetter::Z, 1 | 3) => [(2, 0), (1, 1), (2, 1), (1, 2)],
+        (TetrominoLetter::T, 2) => [(1, 0), (0, 1), (1, 1), (2, 1)],
+        (TetrominoLetter::J, 2) => [(0, 0), (0, 1), (1, 1), (2, 1)],
+        (TetrominoLetter::L, 2) => [(2, 0), (0, 1), (1, 1), (2, 1)],
+        _ => return ars_shape(letter, rotation),
+    };
+    shape_from_cells(cells)
+}
+
+// The spawn states of ARS and NRS sit one row lower in the box, so they start one row higher to match
+const LOW_SPAWN_POSITION: (i32, i32) = (SPAWN_POSITION.0, SPAWN_POSITION.1 + 1);
+
+#[derive(Clone, Debug)]
+pub struct Srs;
+impl RotationSystem for Srs {
+    fn shape(&self, letter: TetrominoLetter, rotation: usize) -> Shape {
+        srs_shape(letter, rotation)
+    }
+
+    fn kicks(&self, letter: TetrominoLetter, from: usize, to: usize) -> Vec<(i32, i32)> {
+        if is_half_turn(from, to) {
+            return vec![];
+        }
+        get_kick_table_scenario(&letter, &from, &to)
+    }
+
+    fn box_clone(&self) -> Box<dyn RotationSystem> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SrsPlus;
+impl RotationSystem for SrsPlus {
+    fn shape(&self, letter: TetrominoLetter, rotation: usize) -> Shape {
+        srs_shape(letter, rotation)
+    }
+
+    fn kicks(&self, letter: TetrominoLetter, from: usize, to: usize) -> Vec<(i32, i32)> {
+        if is_half_turn(from, to) {
+            return get_180_kick_table(letter, from, to);
+        }
+        srs_plus_kicks(letter, from, to)
+    }
+
+    fn box_clone(&self) -> Box<dyn RotationSystem> {
+        Box::new(self.clone())
+    }
+}
+
+// Kicks one cell right, then one cell left. The I piece never kicks.
+// TGM's exception for J, L and T blocked in the center column isn't modelled
+#[derive(Clone, Debug)]
+pub struct Ars;
+impl RotationSystem for Ars {
+    fn shape(&self, letter: TetrominoLetter, rotation: usize) -> Shape {
+        ars_shape(letter, rotation)
+    }
+
+    fn kicks(&self, letter: TetrominoLetter, _from: usize, _to: usize) -> Vec<(i32, i32)> {
+        match letter {
+            TetrominoLetter::I | TetrominoLetter::O => vec![],
+            _ => vec![(0,0), (1,0), (-1,0)],
+        }
+    }
+
+    fn spawn_position(&self, _letter: TetrominoLetter) -> (i32, i32) {
+        LOW_SPAWN_POSITION
+    }
+
+    fn box_clone(&self) -> Box<dyn RotationSystem> {
+        Box::new(self.clone())
+    }
+}
+
+// Turns in place or not at all
+#[derive(Clone, Debug)]
+pub struct Nrs;
+impl RotationSystem for Nrs {
+    fn shape(&self, letter: TetrominoLetter, rotation: usize) -> Shape {
+        nrs_shape(letter, rotation)
+    }
+
+    fn kicks(&self, _letter: TetrominoLetter, _from: usize, _to: usize) -> Vec<(i32, i32)> {
+        vec![]
+    }
+
+    fn spawn_position(&self, _letter: TetrominoLetter) -> (i32, i32) {
+        LOW_SPAWN_POSITION
+    }
+
+    fn box_clone(&self) -> Box<dyn RotationSystem> {
+        Box::new(self.clone())
+    }
+}
+
+// SRS states without any kicks
+#[derive(Clone, Debug)]
+pub struct NoKick;
+impl RotationSystem for NoKick {
+    fn shape(&self, letter: TetrominoLetter, rotation: usize) -> Shape {
+        srs_shape(letter, rotation)
+    }
+
+    fn kicks(&self, _letter: TetrominoLetter, _from: usize, _to: usize) -> Vec<(i32, i32)> {
+        vec![]
+    }
+
+    fn box_clone(&self) -> Box<dyn RotationSystem> {
+        Box::new(self.clone())
+    }
+}
+
+pub fn maybe_try_kicks(
+    piece: &Piece,
+    kick_table: &[(i32, i32)],
+    board: &Board,
+    shape: &Shape,
+) -> Option<(usize, (i32, i32))> {
+    // Rotate your local 4x4 grid.
+    // You apply each offset from the kick table in order:
+    // For each, check: does this new position collide or go out of bounds?
+    // If yes, try next kick
+    // If no, accept offset
+    let current_position_x = piece.position.0;
+    let current_position_y = piece.position.1;
+
+    for (kick_index, &(dx, dy)) in kick_table.iter().enumerate() {
+        // Kicks and board rows both count y upwards, so the offset applies as is
+        let new_x = current_position_x + dx;
+        let new_y = current_position_y + dy;
+        // Is there a collision on the walls?
+        if is_collision(board, (new_x, new_y), shape) {
+            continue; // Out of bounds
+        }
+        // If we reach here, we have a valid position
+        return Some((kick_index, (dx, dy)));
+    }
+    None
+}
+
+pub fn try_rotate(piece: &Piece, board: &Board, rotation_system: &dyn RotationSystem, direction: RotationDirection) -> Option<Piece> {
+    // The rotated piece if it fits, straight away or after a kick
+    if piece.letter == TetrominoLetter::O {
+        return None;
+    }
+    let to_rotation = direction.target_rotation(piece.rotation);
+    let new_shape = rotation_system.shape(piece.letter, to_rotation);
+
+    let (kick_index, (dx, dy)) = if !is_collision(board, piece.position, &new_shape) {
+        (0, (0, 0))
+    } else {
+        let kick_table = rotation_system.kicks(piece.letter, piece.rotation, to_rotation);
+        maybe_try_kicks(piece, &kick_table, board, &new_shape)?
+    };
+
+    let mut rotated = piece.clone();
+    rotated.position.0 += dx;
+    rotated.position.1 += dy;
+    rotated.rotation = to_rotation;
+    rotated.shape = new_shape;
+    rotated.last_rotation_kick = Some(RotationKick { direction, index: kick_index });
+    Some(rotated)
+}
+
+// T-Spins
+const TST_KICK_INDEX: usize = 4; // The last kick of an SRS quarter turn, which always upgrades a mini to a full T-spin
+
+// 180 kick tables are longer, their 5th kick is just another offset
+fn is_tst_kick(kick: RotationKick) -> bool {
+    kick.direction != RotationDirection::Half && kick.index == TST_KICK_INDEX
+}
+
+pub fn detect_t_spin(
+    piece: &Piece,
+    board: &Board
+) -> TSpin {
+    // 3-corner rule: a T piece whose last action was a rotation, with at least 3 of the 4
+    // corners around its center blocked by walls, the floor or locked in cells
+    if piece.letter != TetrominoLetter::T {
+        return TSpin::None;
+    }
+    let Some(kick) = piece.last_rotation_kick else {
+        return TSpin::None;
+    };
+
+    // The center of the T is the only cell with 3 neighbours. It doesn't sit at the same local
+    // position in every rotation system, ARS keeps its states on the bottom of the box
+    let is_filled = |x: i32, y: i32| {
+        x >= 0 && y >= 0 && x < 4 && y < 4 && piece.shape[y as usize][x as usize]
+    };
+    let mut center = None;
+    for y in 0..4 {
+        for x in 0..4 {
+            let neighbours = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                .iter()
+                .filter(|(nx, ny)| is_filled(*nx, *ny))
+                .count();
+            if is_filled(x, y) && neighbours == 3 {
+                center = Some((x, y));
+            }
+        }
+    }
+    let Some((center_x, center_y)) = center else {
+        return TSpin::None;
+    };
+
+    // The T points towards the side that has a cell, the missing side is its back
+    let (point_x, point_y) = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+        .into_iter()
+        .find(|(dx, dy)| !is_filled(center_x - dx, center_y - dy))
+        .unwrap_or((0, -1));
+
+    let is_corner_blocked = |dx: i32, dy: i32| {
+        let board_x = piece.position.0 + center_x + dx;
+        let board_y = piece.position.1 - (center_y + dy);
+        if board_x < 0 || board_x >= BOARD_WIDTH as i32 || board_y < 0 {
+            return true;
+        }
+        if board_y >= BOARD_TOTAL_HEIGHT as i32 {
+            return false;
+        }
+        board.is_blocked(board_x, board_y)
+    };
+
+    // Front corners sit on either side of the point, back corners on either side of the flat side
+    let (side_x, side_y) = (point_y, point_x);
+    let front_corners = [
+        is_corner_blocked(point_x + side_x, point_y + side_y),
+        is_corner_blocked(point_x - side_x, point_y - side_y),
+    ].iter().filter(|blocked| **blocked).count();
+    let back_corners = [
+        is_corner_blocked(-point_x + side_x, -point_y + side_y),
+        is_corner_blocked(-point_x - side_x, -point_y - side_y),
+    ].iter().filter(|blocked| **blocked).count();
+
+    if front_corners + back_corners < 3 {
+        TSpin::None
+    } else if front_corners == 2 || is_tst_kick(kick) {
+        TSpin::Full
+    } else {
+        TSpin::Mini
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::board::Cell;
+    use crate::core::piece::shape_cells;
+
+    // (from, to) rotation states and their kicks
+    type KickRow = ((usize, usize), [(i32, i32); 5]);
+
+    const JLSTZ_REFERENCE: [KickRow; 8] = [
+        ((0, 1), [(0,0), (-1,0), (-1,1), (0,-2), (-1,-2)]),
+        ((1, 0), [(0,0), (1,0), (1,-1), (0,2), (1,2)]),
+        ((1, 2), [(0,0), (1,0), (1,-1), (0,2), (1,2)]),
+        ((2, 1), [(0,0), (-1,0), (-1,1), (0,-2), (-1,-2)]),
+        ((2, 3), [(0,0), (1,0), (1,1), (0,-2), (1,-2)]),
+        ((3, 2), [(0,0), (-1,0), (-1,-1), (0,2), (-1,2)]),
+        ((3, 0), [(0,0), (-1,0), (-1,-1), (0,2), (-1,2)]),
+        ((0, 3), [(0,0), (1,0), (1,1), (0,-2), (1,-2)]),
+    ];
+    const I_REFERENCE: [KickRow; 8] = [
+        ((0, 1), [(0,0), (-2,0), (1,0), (-2,-1), (1,2)]),
+        ((1, 0), [(0,0), (2,0), (-1,0), (2,1), (-1,-2)]),
+        ((1, 2), [(0,0), (-1,0), (2,0), (-1,2), (2,-1)]),
+        ((2, 1), [(0,0), (1,0), (-2,0), (1,-2), (-2,1)]),
+        ((2, 3), [(0,0), (2,0), (-1,0), (2,1), (-1,-2)]),
+        ((3, 2), [(0,0), (-2,0), (1,0), (-2,-1), (1,2)]),
+        ((3, 0), [(0,0), (1,0), (-2,0), (1,-2), (-2,1)]),
+        ((0, 3), [(0,0), (-1,0), (2,0), (-1,2), (2,-1)]),
+    ];
+    const I_SRS_PLUS_REFERENCE: [KickRow; 8] = [
+        ((0, 1), [(0,0), (1,0), (-2,0), (-2,-1), (1,2)]),
+        ((1, 0), [(0,0), (-1,0), (2,0), (-1,-2), (2,1)]),
+        ((1, 2), [(0,0), (-1,0), (2,0), (-1,2), (2,-1)]),
+        ((2, 1), [(0,0), (-2,0), (1,0), (-2,1), (1,-2)]),
+        ((2, 3), [(0,0), (2,0), (-1,0), (2,1), (-1,-2)]),
+        ((3, 2), [(0,0), (1,0), (-2,0), (1,-2), (-2,1)]),
+        ((3, 0), [(0,0), (1,0), (-2,0), (1,-2), (-2,1)]),
+        ((0, 3), [(0,0), (-1,0), (2,0), (2,1), (-1,-2)]),
+    ];
+    const JLSTZ: [TetrominoLetter; 5] = [TetrominoLetter::J, TetrominoLetter::L, TetrominoLetter::S, TetrominoLetter::T, TetrominoLetter::Z];
+
+    // Rows are written top down, the last one is the floor
+    fn board_from_rows(rows: &[&str]) -> Board {
+        let mut board = Board::new();
+        for (index, row) in rows.iter().enumerate() {
+            let y = (rows.len() - 1 - index) as i32;
+            for (x, cell) in row.chars().enumerate() {
+                if cell == 'X' {
+                    board.cells[Board::index(x as i32, y)] = Cell::Filled(TetrominoLetter::O);
+                }
+            }
+        }
+        board
+    }
+
+    fn t_piece(rotation: usize, position: (i32, i32)) -> Piece {
+        Piece {
+            shape: Srs.shape(TetrominoLetter::T, rotation),
+            position,
+            rotation,
+            letter: TetrominoLetter::T,
+            last_rotation_kick: None,
+        }
+    }
+
+    fn filled_cells(board: &Board) -> Vec<(i32, i32)> {
+        let mut cells = vec![];
+        for y in 0..BOARD_TOTAL_HEIGHT as i32 {
+            for x in 0..BOARD_WIDTH as i32 {
+                if board.is_blocked(x, y) {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn srs_kicks_match_the_reference_tables() {
+        for ((from, to), kicks) in JLSTZ_REFERENCE {
+            for letter in JLSTZ {
+                assert_eq!(get_kick_table_scenario(&letter, &from, &to), kicks.to_vec(), "{:?} {}->{}", letter, from, to);
+                assert_eq!(Srs.kicks(letter, from, to), kicks.to_vec(), "{:?} {}->{}", letter, from, to);
+            }
+        }
+        for ((from, to), kicks) in I_REFERENCE {
+            assert_eq!(get_kick_table_scenario(&TetrominoLetter::I, &from, &to), kicks.to_vec(), "I {}->{}", from, to);
+            assert_eq!(Srs.kicks(TetrominoLetter::I, from, to), kicks.to_vec(), "I {}->{}", from, to);
+        }
+        assert!(Srs.kicks(TetrominoLetter::O, 0, 1).is_empty());
+    }
+
+    #[test]
+    fn srs_plus_kicks_match_the_reference_tables() {
+        // JLSTZ are plain SRS, only I differs
+        for ((from, to), kicks) in JLSTZ_REFERENCE {
+            for letter in JLSTZ {
+                assert_eq!(SrsPlus.kicks(letter, from, to), kicks.to_vec(), "{:?} {}->{}", letter, from, to);
+            }
+        }
+        for ((from, to), kicks) in I_SRS_PLUS_REFERENCE {
+            assert_eq!(SrsPlus.kicks(TetrominoLetter::I, from, to), kicks.to_vec(), "I {}->{}", from, to);
+        }
+        for letter in TetrominoLetter::ALL {
+            for from in 0..4 {
+                let to = (from + 2) % 4;
+                assert_eq!(SrsPlus.kicks(letter, from, to), get_180_kick_table(letter, from, to));
+                assert!(Srs.kicks(letter, from, to).is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn srs_states_turn_around_the_pivot() {
+        let cells = |shape: Shape| shape_cells(&shape, (0, 0)).map(|(x, y)| (x, -y)).collect::<Vec<_>>();
+        assert_eq!(cells(srs_shape(TetrominoLetter::T, 1)), vec![(1, 0), (1, 1), (2, 1), (1, 2)]);
+        assert_eq!(cells(srs_shape(TetrominoLetter::T, 2)), vec![(0, 1), (1, 1), (2, 1), (1, 2)]);
+        assert_eq!(cells(srs_shape(TetrominoLetter::J, 1)), vec![(1, 0), (2, 0), (1, 1), (1, 2)]);
+        assert_eq!(cells(srs_shape(TetrominoLetter::L, 3)), vec![(0, 0), (1, 0), (1, 1), (1, 2)]);
+        assert_eq!(cells(srs_shape(TetrominoLetter::S, 1)), vec![(1, 0), (1, 1), (2, 1), (2, 2)]);
+        assert_eq!(cells(srs_shape(TetrominoLetter::Z, 3)), vec![(1, 0), (0, 1), (1, 1), (0, 2)]);
+        assert_eq!(cells(srs_shape(TetrominoLetter::I, 1)), vec![(2, 0), (2, 1), (2, 2), (2, 3)]);
+        assert_eq!(cells(srs_shape(TetrominoLetter::I, 2)), vec![(0, 2), (1, 2), (2, 2), (3, 2)]);
+        assert_eq!(cells(srs_shape(TetrominoLetter::I, 3)), vec![(1, 0), (1, 1), (1, 2), (1, 3)]);
+    }
+
+    #[test]
+    fn t_spin_triple_takes_the_last_kick() {
+        let mut board = board_from_rows(&[
+            "..X.......",
+            "..........",
+            "XX.XXXXXXX",
+            "X..XXXXXXX",
+            "XX.XXXXXXX",
+        ]);
+        let piece = t_piece(0, (0, 4));
+        assert!(!piece.collides(&board));
+
+        let rotated = try_rotate(&piece, &board, &Srs, RotationDirection::CounterClockwise).unwrap();
+        assert_eq!(rotated.position, (1, 2));
+        assert_eq!(rotated.last_rotation_kick.map(|kick| kick.index), Some(TST_KICK_INDEX));
+        assert_eq!(detect_t_spin(&rotated, &board), TSpin::Full);
+        board.place(&rotated);
+        assert_eq!(board.clear_full_rows(), 3);
+    }
+
+    #[test]
+    fn dt_cannon_double_then_triple() {
+        // The DT cannon's shape: a T-spin double whose clear opens up a T-spin triple slot underneath
+        let mut board = board_from_rows(&[
+            "..X.......",
+            ".....X..X.",
+            "XXXXX...XX",
+            "XXXXXX.XXX",
+            "XX.XXXXXXX",
+            "XX..XXXXXX",
+            "XX.XXXXXXX",
+        ]);
+
+        let double = t_piece(3, (6, 6));
+        assert!(!double.collides(&board));
+        let double = try_rotate(&double, &board, &Srs, RotationDirection::CounterClockwise).unwrap();
+        assert_eq!(double.position, (5, 5));
+        assert_eq!(double.last_rotation_kick.map(|kick| kick.index), Some(2));
+        assert_eq!(detect_t_spin(&double, &board), TSpin::Full);
+        board.place(&double);
+        assert_eq!(board.clear_full_rows(), 2);
+
+        let triple = t_piece(0, (2, 4));
+        assert!(!triple.collides(&board));
+        let triple = try_rotate(&triple, &board, &Srs, RotationDirection::Clockwise).unwrap();
+        assert_eq!(triple.position, (1, 2));
+        assert_eq!(triple.last_rotation_kick.map(|kick| kick.index), Some(TST_KICK_INDEX));
+        assert_eq!(detect_t_spin(&triple, &board), TSpin::Full);
+        board.place(&triple);
+        assert_eq!(board.clear_full_rows(), 3);
+        assert_eq!(filled_cells(&board), vec![(5, 0), (8, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn half_turn_fifth_kick_stays_a_mini() {
+        // Only one front corner is blocked, so the 180's 5th kick mustn't count as a T-spin triple kick
+        let board = board_from_rows(&[
+            ".X........",
+            "..........",
+            ".X.X......",
+            "...X......",
+            "..........",
+        ]);
+        let piece = t_piece(3, (1, 2));
+        assert!(!piece.collides(&board));
+
+        let rotated = try_rotate(&piece, &board, &SrsPlus, RotationDirection::Half).unwrap();
+        assert_eq!(rotated.position, (1, 4));
+        assert_eq!(rotated.last_rotation_kick, Some(RotationKick { direction: RotationDirection::Half, index: 4 }));
+        assert_eq!(detect_t_spin(&rotated, &board), TSpin::Mini);
+    }
+
+    #[test]
+    fn only_srs_plus_kicks_half_turns() {
+        let board = board_from_rows(&[
+            "..........",
+            "..........",
+            ".X........",
+            "XXX.XXXXXX",
+        ]);
+        let piece = t_piece(0, (0, 3));
+        assert!(!piece.collides(&board));
+
+        // Turned in place the T would poke into the floor, SRS+ kicks it up a row
+        assert_eq!(try_rotate(&piece, &board, &Srs, RotationDirection::Half), None);
+        let rotated = try_rotate(&piece, &board, &SrsPlus, RotationDirection::Half).unwrap();
+        assert_eq!(rotated.position, (0, 4));
+        assert_eq!(rotated.rotation, 2);
+
+        // With room to turn in place both agree
+        let open = Board::new();
+        let piece = t_piece(0, (3, 10));
+        assert_eq!(try_rotate(&piece, &open, &Srs, RotationDirection::Half), try_rotate(&piece, &open, &SrsPlus, RotationDirection::Half));
+    }
+}
\ No newline at end of file
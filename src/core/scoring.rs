@@ -0,0 +1,292 @@
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
+#[derive(Clone, Debug)]
+pub struct Scoring {
+    pub level: usize,
+    pub start_level: usize,
+    pub score: usize,
+    pub lines_cleared: usize,
+    pub goal_points: usize, // Lines awarded towards the variable level up goal
+    pub combo: Option<usize>, // None until a lock clears lines, then counts every consecutive clear after that
+    pub back_to_back: Option<usize>, // None until a difficult clear, then counts every consecutive difficult clear after that
+}
+impl Scoring {
+    pub fn new(start_level: usize) -> Self {
+        Scoring { level: start_level, start_level, score: 0, lines_cleared: 0, goal_points: 0, combo: None, back_to_back: None }
+    }
+
+    pub fn score_lock(&mut self, lines_cleared_at_once: usize, t_spin: TSpin) -> usize {
+        // Updates the combo and back-to-back chains and adds the points for a piece that just locked in
+        let mut points = calculate_score(lines_cleared_at_once, self.level, t_spin);
+
+        if lines_cleared_at_once == 0 {
+            // Nothing cleared breaks the combo, but keeps the back-to-back chain going
+            self.combo = None;
+            self.score += points;
+            return points;
+        }
+
+        // Combo bonus for every consecutive clear after the first
+        let combo = self.combo.map_or(0, |combo| combo + 1);
+        self.combo = Some(combo);
+        points += 50 * combo * self.level;
+
+        // Tetrises and T-spins that clear lines are difficult, chaining them earns 1.5x.
+        // Any other line clear breaks the chain
+        if is_difficult_clear(lines_cleared_at_once, t_spin) {
+            if let Some(chain) = self.back_to_back {
+                let line_clear_points = calculate_score(lines_cleared_at_once, self.level, t_spin);
+                points += line_clear_points / 2;
+                self.back_to_back = Some(chain + 1);
+            } else {
+                self.back_to_back = Some(0);
+            }
+        } else {
+            self.back_to_back = None;
+        }
+
+        self.score += points;
+        points
+    }
+}
+
+// Points that aren't tied to line clears. Swap them out to play with different rules
+#[derive(Clone, Copy, Debug)]
+pub struct ScoringRules {
+    pub soft_drop_points_per_row: usize,
+    pub hard_drop_points_per_row: usize,
+}
+impl ScoringRules {
+    pub fn guideline() -> Self {
+        ScoringRules { soft_drop_points_per_row: 1, hard_drop_points_per_row: 2 }
+    }
+}
+
+// How many lines it takes to reach the next level
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LevelUpRule {
+    Fixed, // Every 10 lines
+    Variable, // 5 times the level, with bigger clears awarding more lines
+}
+impl LevelUpRule {
+    pub fn next(&self) -> Self {
+        match self {
+            LevelUpRule::Fixed => LevelUpRule::Variable,
+            LevelUpRule::Variable => LevelUpRule::Fixed,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        self.next()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LevelUpRule::Fixed => "Fixed 10 Lines",
+            LevelUpRule::Variable => "Variable Goal",
+        }
+    }
+
+    pub fn award(&self, lines_cleared_at_once: usize, t_spin: TSpin, back_to_back: bool) -> usize {
+        // Lines awarded towards the variable goal, a back-to-back clear adds half again
+        let awarded = match (t_spin, lines_cleared_at_once) {
+            (TSpin::None, 1) => 1,
+            (TSpin::None, 2) => 3,
+            (TSpin::None, 3) => 5,
+            (TSpin::None, 4) => 8,
+            (TSpin::Mini, 0) => 1,
+            (TSpin::Mini, 1) => 2,
+            (TSpin::Mini, 2) => 4,
+            (TSpin::Full, 0) => 4,
+            (TSpin::Full, 1) => 8,
+            (TSpin::Full, 2) => 12,
+            (TSpin::Full, 3) => 16,
+            _ => 0,
+        };
+        if back_to_back { awarded + awarded / 2 } else { awarded }
+    }
+
+    pub fn level_for(&self, scoring: &Scoring) -> usize {
+        match self {
+            LevelUpRule::Fixed => calculate_level(&scoring.lines_cleared, scoring.start_level),
+            LevelUpRule::Variable => {
+                // Starting higher only means the first goal is bigger
+                let mut level = scoring.start_level;
+                let mut points_left = scoring.goal_points;
+                while points_left >= 5 * level {
+                    points_left -= 5 * level;
+                    level += 1;
+                }
+                level
+            }
+        }
+    }
+}
+
+pub const MAX_START_LEVEL: usize = 15;
+
+// Stats that don't affect the score but are shown on the results screen
+#[derive(Clone, Debug, Default)]
+pub struct GameStats {
+    pub play_time: Duration, // Only counts time the game was ticked for, so pauses don't count
+    pub pieces_placed: usize,
+    pub tetrises: usize,
+    pub max_combo: usize,
+}
+impl GameStats {
+    pub fn record_lock(&mut self, lines_cleared_at_once: usize, combo: Option<usize>) {
+        self.pieces_placed += 1;
+        if lines_cleared_at_once == 4 {
+            self.tetrises += 1;
+        }
+        self.max_combo = self.max_combo.max(combo.unwrap_or(0));
+    }
+
+    pub fn pieces_per_second(&self) -> f32 {
+        let seconds = self.play_time.as_secs_f32();
+        if seconds > 0.0 { self.pieces_placed as f32 / seconds } else { 0.0 }
+    }
+}
+
+pub fn is_difficult_clear(lines_cleared_at_once: usize, t_spin: TSpin) -> bool {
+    lines_cleared_at_once == 4 || (lines_cleared_at_once > 0 && t_spin != TSpin::None)
+}
+
+pub fn calculate_level(total_lines_cleared: &usize, start_level: usize) -> usize {
+    // Like the NES, starting high delays the first level up so the early levels still have to be earned.
+    // After that it's every 10 lines
+    let start = start_level.saturating_sub(1);
+    let first_level_up = (start * 10 + 10).min((start * 10).saturating_sub(50).max(100));
+    if *total_lines_cleared < first_level_up {
+        start_level
+    } else {
+        start_level + 1 + (total_lines_cleared - first_level_up) / 10
+    }
+}
+
+pub fn calculate_score(lines_cleared_at_once: usize, level: usize, t_spin: TSpin) -> usize {
+    match (t_spin, lines_cleared_at_once) {
+        (TSpin::None, 1) => 100 * level,
+        (TSpin::None, 2) => 300 * level,
+        (TSpin::None, 3) => 500 * level,
+        (TSpin::None, 4) => 800 * level,
+        (TSpin::Mini, 0) => 100 * level,
+        (TSpin::Mini, 1) => 200 * level,
+        (TSpin::Mini, 2) => 400 * level,
+        (TSpin::Full, 0) => 400 * level,
+        (TSpin::Full, 1) => 800 * level,
+        (TSpin::Full, 2) => 1200 * level,
+        (TSpin::Full, 3) => 1600 * level,
+        _ => 0 // Should never happen
+    }
+}
+
+pub fn calculate_perfect_clear_bonus(lines_cleared_at_once: usize, level: usize, back_to_back: bool) -> usize {
+    // Added on top of the normal line clear score
+    match (lines_cleared_at_once, back_to_back) {
+        (1, _) => 800 * level,
+        (2, _) => 1200 * level,
+        (3, _) => 1800 * level,
+        (4, false) => 2000 * level,
+        (4, true) => 3200 * level,
+        _ => 0 // Should never happen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combos_add_fifty_per_level_for_every_clear_in_a_row() {
+        let mut scoring = Scoring::new(2);
+        assert_eq!(scoring.score_lock(1, TSpin::None), 200);
+        assert_eq!(scoring.score_lock(1, TSpin::None), 200 + 100);
+        assert_eq!(scoring.score_lock(2, TSpin::None), 600 + 200);
+        assert_eq!(scoring.combo, Some(2));
+
+        // A lock that clears nothing ends the combo
+        assert_eq!(scoring.score_lock(0, TSpin::None), 0);
+        assert_eq!(scoring.combo, None);
+        assert_eq!(scoring.score_lock(1, TSpin::None), 200);
+        assert_eq!(scoring.score, 200 + 300 + 800 + 200);
+    }
+
+    #[test]
+    fn back_to_back_difficult_clears_earn_half_again() {
+        let mut scoring = Scoring::new(1);
+        assert_eq!(scoring.score_lock(4, TSpin::None), 800);
+        assert_eq!(scoring.back_to_back, Some(0));
+
+        // Tetris after a tetris: 800, half again for back-to-back and 50 for the combo
+        assert_eq!(scoring.score_lock(4, TSpin::None), 800 + 400 + 50);
+        assert_eq!(scoring.back_to_back, Some(1));
+
+        // A T-spin that clears nothing keeps the chain but ends the combo
+        assert_eq!(scoring.score_lock(0, TSpin::Full), 400);
+        assert_eq!(scoring.back_to_back, Some(1));
+        assert_eq!(scoring.score_lock(2, TSpin::Full), 1200 + 600);
+        assert_eq!(scoring.back_to_back, Some(2));
+
+        // Any easier clear breaks it
+        assert_eq!(scoring.score_lock(1, TSpin::None), 100 + 50);
+        assert_eq!(scoring.back_to_back, None);
+        assert_eq!(scoring.score_lock(4, TSpin::None), 800 + 100);
+    }
+
+    #[test]
+    fn line_clears_and_perfect_clears_pay_the_guideline_table() {
+        assert_eq!(calculate_score(1, 3, TSpin::None), 300);
+        assert_eq!(calculate_score(4, 3, TSpin::None), 2400);
+        assert_eq!(calculate_score(0, 3, TSpin::Mini), 300);
+        assert_eq!(calculate_score(2, 3, TSpin::Mini), 1200);
+        assert_eq!(calculate_score(3, 3, TSpin::Full), 4800);
+
+        assert_eq!(calculate_perfect_clear_bonus(1, 1, false), 800);
+        assert_eq!(calculate_perfect_clear_bonus(2, 2, true), 2400);
+        assert_eq!(calculate_perfect_clear_bonus(3, 1, false), 1800);
+        assert_eq!(calculate_perfect_clear_bonus(4, 1, false), 2000);
+        assert_eq!(calculate_perfect_clear_bonus(4, 1, true), 3200);
+    }
+
+    #[test]
+    fn starting_higher_delays_the_first_level_up() {
+        // (start level, lines for the first level up)
+        for (start_level, first_level_up) in [(0, 10), (1, 10), (5, 50), (10, 100), (15, 100)] {
+            assert_eq!(calculate_level(&(first_level_up - 1), start_level), start_level, "start {}", start_level);
+            assert_eq!(calculate_level(&first_level_up, start_level), start_level + 1, "start {}", start_level);
+            assert_eq!(calculate_level(&(first_level_up + 19), start_level), start_level + 2, "start {}", start_level);
+            assert_eq!(calculate_level(&(first_level_up + 20), start_level), start_level + 3, "start {}", start_level);
+        }
+    }
+
+    #[test]
+    fn level_up_rules() {
+        let mut scoring = Scoring::new(1);
+        scoring.lines_cleared = 25;
+        assert_eq!(LevelUpRule::Fixed.level_for(&scoring), 3);
+
+        // Variable goals are 5 times the level: 5 points to leave level 1, then 10 more to leave level 2
+        for (goal_points, level) in [(4, 1), (5, 2), (14, 2), (15, 3), (30, 4)] {
+            scoring.goal_points = goal_points;
+            assert_eq!(LevelUpRule::Variable.level_for(&scoring), level, "{} points", goal_points);
+        }
+        scoring.start_level = 3;
+        scoring.goal_points = 14;
+        assert_eq!(LevelUpRule::Variable.level_for(&scoring), 3);
+
+        assert_eq!(LevelUpRule::Variable.award(1, TSpin::None, false), 1);
+        assert_eq!(LevelUpRule::Variable.award(4, TSpin::None, false), 8);
+        assert_eq!(LevelUpRule::Variable.award(4, TSpin::None, true), 12);
+        assert_eq!(LevelUpRule::Variable.award(0, TSpin::Full, false), 4);
+        assert_eq!(LevelUpRule::Variable.award(3, TSpin::Full, true), 24);
+        assert_eq!(LevelUpRule::Variable.award(0, TSpin::None, false), 0);
+    }
+}
\ No newline at end of file
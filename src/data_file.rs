@@ -0,0 +1,71 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::Lines;
+
+use bevy::prelude::*;
+
+// Small text files kept between runs, like the high scores and the controls. Each one starts with a
+// "<header> <version>" line so later versions can tell what they're reading
+
+pub fn data_file_path(file_name: &str) -> Option<PathBuf> {
+    // A file in the platform's per-user data directory
+    let data_dir = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+    };
+    data_dir.map(|dir| dir.join("tetris_in_rust").join(file_name))
+}
+
+// The same path with something added to the end of the file name, "scores.txt" becomes "scores.txt.tmp"
+fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+pub fn read_header<'a>(contents: &'a str, header: &str, version: u32) -> Result<Lines<'a>, String> {
+    // Checks the first line and hands back the rest. Files from newer versions are still read, as far as this version understands them
+    let mut lines = contents.lines();
+    let first_line = lines.next().ok_or("empty file")?;
+    let file_version = first_line
+        .strip_prefix(header)
+        .and_then(|file_version| file_version.trim().parse::<u32>().ok())
+        .ok_or(format!("unrecognised header {:?}", first_line))?;
+    if file_version > version {
+        warn!("{} file is version {}, reading the fields version {} knows about", header, file_version, version);
+    }
+    Ok(lines)
+}
+
+pub fn read_data_file<T>(path: &Path, parse: impl FnOnce(&str) -> Result<T, String>) -> Option<T> {
+    // A missing file just means nothing has been saved yet
+    let contents = fs::read_to_string(path).ok()?;
+    match parse(&contents) {
+        Ok(loaded) => Some(loaded),
+        Err(error) => {
+            // Keep the broken file around instead of overwriting it on the next save
+            warn!("Couldn't read {}: {}", path.display(), error);
+            let _ = fs::rename(path, path_with_suffix(path, ".corrupted"));
+            None
+        }
+    }
+}
+
+pub fn write_data_file(path: &Path, contents: &str) {
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+
+    // Write to a temporary file first so a crash mid-save can't corrupt it
+    let temporary_path = path_with_suffix(path, ".tmp");
+    let result = fs::write(&temporary_path, contents)
+        .and_then(|_| fs::rename(&temporary_path, path));
+    if let Err(error) = result {
+        warn!("Couldn't save {}: {}", path.display(), error);
+    }
+}
\ No newline at end of file
@@ -1,102 +1,377 @@
 use::bevy::prelude::*;
+use bevy::window::WindowFocused;
 
-use crate::grid::{GridConfig, GRID_CELL_SIZE, GRID_WIDTH, GRID_HEIGHT};
+use crate::core::BOARD_TOTAL_HEIGHT;
+use crate::grid::{GridConfig, RedrawGridEvent, GRID_CELL_SIZE, GRID_WIDTH, GRID_HEIGHT};
+use crate::play::GameCore;
+use crate::scoring::format_play_time;
+use crate::high_scores::is_entering_name;
+use crate::mode::GameMode;
+use crate::controls::{Action, KeyBindings};
 
 pub struct GameManagerPlugin;
 impl Plugin for GameManagerPlugin{
     fn build(&self, app: &mut App){
         app
-            .insert_resource(GameState { started: false })
-            .add_event::<GameStartEvent>()
-            .add_event::<GameRestartEvent>()
+            .init_state::<AppState>()
+            .insert_resource(CountdownTimer(Timer::from_seconds(COUNTDOWN_SECONDS, TimerMode::Once)))
+            .insert_resource(GameOverAnimation { timer: Timer::from_seconds(GAME_OVER_ROW_SECONDS, TimerMode::Repeating), rows_greyed: 0 })
+            .insert_resource(GameResult::Lost)
             .add_event::<GameLoseEvent>()
-            .add_systems(Update, (detect_start_game, detect_restart_game, spawn_lose_text, animate_lose_text, reset_lose_text));
+            .add_event::<GameFinishEvent>()
+            .add_systems(OnEnter(AppState::Title), spawn_title_screen)
+            .add_systems(OnExit(AppState::Title), despawn_screen::<TitleScreen>)
+            .add_systems(OnEnter(AppState::Countdown), (spawn_countdown_text, start_game_over_animation))
+            .add_systems(OnExit(AppState::Countdown), despawn_screen::<CountdownText>)
+            .add_systems(OnEnter(AppState::Paused), spawn_pause_overlay)
+            .add_systems(OnExit(AppState::Paused), despawn_pause_overlay)
+            .add_systems(OnEnter(AppState::GameOver), (spawn_lose_text, start_game_over_animation))
+            .add_systems(OnExit(AppState::GameOver), despawn_screen::<AnimateLoseText>)
+            .add_systems(OnEnter(AppState::Results), spawn_results_screen)
+            .add_systems(OnExit(AppState::Results), despawn_screen::<ResultsScreen>)
+            .add_systems(Update, detect_start_game.run_if(in_state(AppState::Title)))
+            .add_systems(Update, update_countdown.run_if(in_state(AppState::Countdown)))
+            .add_systems(Update, (detect_pause_game, detect_restart_game, detect_lose_game, detect_finish_game).run_if(in_state(AppState::Playing)))
+            .add_systems(Update, detect_pause_menu_input.run_if(in_state(AppState::Paused)))
+            .add_systems(Update, (animate_lose_text, animate_game_over, detect_dismiss_game_over).run_if(in_state(AppState::GameOver)))
+            .add_systems(Update, detect_dismiss_results.run_if(in_state(AppState::Results).and(not(is_entering_name))));
     }
 }
 
+// The whole flow of the app. Every plugin hooks its setup and cleanup onto these
+// with OnEnter/OnExit and gates its gameplay systems with in_state(AppState::Playing)
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AppState {
+    #[default]
+    Title,
+    Menu,
+    Controls,
+    Countdown, // Board is reset when this is entered, the first piece spawns when it's left
+    Playing,
+    Paused,
+    GameOver,
+    Results,
+}
 
 #[derive(Event)]
-pub struct GameStartEvent;
+pub struct GameLoseEvent;
 
+// Sent when the mode's goal is reached, like clearing the last line of a sprint
 #[derive(Event)]
-pub struct GameLoseEvent;
+pub struct GameFinishEvent;
+
+// How the last game ended
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    Lost,
+    Finished,
+}
+
+const COUNTDOWN_SECONDS: f32 = 3.0;
+
+#[derive(Resource)]
+pub struct CountdownTimer(pub Timer);
+
+const GAME_OVER_ROW_SECONDS: f32 = 0.05;
+const GAME_OVER_HOLD_ROWS: usize = 20; // Extra ticks to leave the greyed out board up before the results
 
 #[derive(Resource)]
-pub struct GameState {
-    pub started: bool,
+pub struct GameOverAnimation {
+    pub timer: Timer,
+    pub rows_greyed: usize, // The grid draws every row below this grey
+}
+
+// Components
+#[derive(Component)]
+pub struct TitleScreen;
+
+#[derive(Component)]
+pub struct CountdownText;
+
+#[derive(Component)]
+pub struct PauseOverlay;
+
+#[derive(Component)]
+pub struct AnimateLoseText;
+
+#[derive(Component)]
+pub struct ResultsScreen;
+
+// Helpers
+pub fn spawn_screen_backdrop(
+    commands: &mut Commands,
+    window_query: &Query<&Window>,
+    marker: impl Component,
+){
+    // Covers the whole window so nothing from the board shows through
+    let window_size = window_query.iter().next().map_or(Vec2::new(1920.0, 1080.0), |window| window.size());
+    commands.spawn((
+        Sprite::from_color(Color::srgb(0.05, 0.05, 0.1), window_size),
+        Transform::from_xyz(0.0, 0.0, 50.0),
+        marker
+    ));
+}
+
+pub fn despawn_screen<T: Component>(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<T>>,
+){
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// Title
+pub fn spawn_title_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    window_query: Query<&Window>,
+){
+    let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+    spawn_screen_backdrop(&mut commands, &window_query, TitleScreen {});
+
+    commands.spawn((
+        Text2d::new("Tetris"),
+        TextColor(Color::srgb(0.8, 0.85, 0.9)),
+        TextFont {
+            font: font.clone(),
+            font_size: 150.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(0.0, 100.0, 51.0),
+        TitleScreen {}
+    ));
+
+    commands.spawn((
+        Text2d::new("Press ENTER\nC for controls"),
+        TextColor(Color::srgb(0.8, 0.85, 0.9)),
+        TextFont {
+            font: font.clone(),
+            font_size: 30.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(0.0, -50.0, 51.0),
+        TitleScreen {}
+    ));
 }
 
 pub fn detect_start_game(
-    mut game_start_event: EventWriter<GameStartEvent>,
-    mut game_state: ResMut<GameState>,
-    keyboard_input: Res<ButtonInput<KeyCode>>
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
 ) {
     // Detect if I press enter key
-    if !game_state.started {
-        if keyboard_input.just_pressed(KeyCode::Enter) {
-            // Send the game start event
-            game_start_event.send(GameStartEvent);
-            game_state.started = true;
-        }
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        next_state.set(AppState::Menu);
+    } else if keyboard_input.just_pressed(KeyCode::KeyC) {
+        next_state.set(AppState::Controls);
     }
 }
 
-#[derive(Event)]
-pub struct GameRestartEvent;
+// Countdown
+pub fn spawn_countdown_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    grid_config: Res<GridConfig>,
+    mut countdown_timer: ResMut<CountdownTimer>,
+){
+    countdown_timer.0.reset();
+
+    let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+    let text_x = grid_config.start_x + ((GRID_WIDTH as f32 / 2.0) * GRID_CELL_SIZE);
+    let text_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE);
+
+    commands.spawn((
+        Text2d::new(format!("{}", COUNTDOWN_SECONDS.ceil())),
+        TextColor(Color::srgb(0.8, 0.85, 0.9)),
+        TextFont {
+            font: font.clone(),
+            font_size: 150.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(text_x, text_y, 1.0),
+        CountdownText {}
+    ));
+}
+
+pub fn update_countdown(
+    time: Res<Time>,
+    mut countdown_timer: ResMut<CountdownTimer>,
+    mut countdown_text_query: Query<&mut Text2d, With<CountdownText>>,
+    mut next_state: ResMut<NextState<AppState>>,
+){
+    countdown_timer.0.tick(time.delta());
+    if countdown_timer.0.finished() {
+        next_state.set(AppState::Playing);
+        return;
+    }
+
+    let seconds_left = countdown_timer.0.remaining_secs().ceil();
+    for mut text in &mut countdown_text_query {
+        text.0 = format!("{}", seconds_left);
+    }
+}
 
+// Playing
 pub fn detect_restart_game(
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut game_state: ResMut<GameState>,
-    mut game_restart_event: EventWriter<GameRestartEvent>,
-    mut game_lose_event: EventReader<GameLoseEvent>
+    key_bindings: Res<KeyBindings>,
+    mut next_state: ResMut<NextState<AppState>>,
 ){
-    // Send GameRestartEvent
-    if keyboard_input.just_pressed(KeyCode::KeyR) {
-        game_state.started = false;
-        game_restart_event.send(GameRestartEvent);
+    // Going back through the countdown resets the board
+    if key_bindings.just_pressed(&keyboard_input, Action::Restart) {
+        next_state.set(AppState::Countdown);
     }
-    // This will be getting triggered when we detect game is lost
+}
+
+pub fn detect_lose_game(
+    mut game_lose_event: EventReader<GameLoseEvent>,
+    mut game_result: ResMut<GameResult>,
+    mut next_state: ResMut<NextState<AppState>>,
+){
     if !game_lose_event.is_empty() {
         game_lose_event.clear();
-        game_state.started = false;
-        game_restart_event.send(GameRestartEvent);
+        *game_result = GameResult::Lost;
+        next_state.set(AppState::GameOver);
     }
 }
 
-#[derive(Component)]
-pub struct AnimateLoseText;
+pub fn detect_finish_game(
+    mut game_finish_event: EventReader<GameFinishEvent>,
+    mut game_result: ResMut<GameResult>,
+    mut next_state: ResMut<NextState<AppState>>,
+){
+    if !game_finish_event.is_empty() {
+        game_finish_event.clear();
+        *game_result = GameResult::Finished;
+        next_state.set(AppState::GameOver);
+    }
+}
 
-pub fn spawn_lose_text(
+pub fn detect_pause_game(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    mut window_focused_event: EventReader<WindowFocused>,
+    mut next_state: ResMut<NextState<AppState>>,
+){
+    // Losing window focus pauses automatically
+    let pause_pressed = key_bindings.just_pressed(&keyboard_input, Action::Pause);
+    let focus_lost = window_focused_event.read().any(|event| !event.focused);
+    if pause_pressed || focus_lost {
+        next_state.set(AppState::Paused);
+    }
+}
+
+// Paused
+pub fn detect_pause_menu_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut app_exit_event: EventWriter<AppExit>,
+){
+    if key_bindings.just_pressed(&keyboard_input, Action::Pause) {
+        next_state.set(AppState::Playing);
+    } else if key_bindings.just_pressed(&keyboard_input, Action::Restart) {
+        next_state.set(AppState::Countdown);
+    } else if keyboard_input.just_pressed(KeyCode::KeyQ) {
+        app_exit_event.send(AppExit::Success);
+    }
+}
+
+pub fn spawn_pause_overlay(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     grid_config: Res<GridConfig>,
-    mut game_lose_event: EventReader<GameLoseEvent>
+    key_bindings: Res<KeyBindings>,
+    window_query: Query<&Window>,
+    mut virtual_time: ResMut<Time<Virtual>>,
 ){
-    if !game_lose_event.is_empty() {
-        game_lose_event.clear();
+    // Freezing virtual time stops every timer that runs off Res<Time>
+    virtual_time.pause();
 
-        let font = asset_server.load("fonts/gg-sans-Regular.ttf");
-        let text_font = TextFont {
+    let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+    let text_x = grid_config.start_x + ((GRID_WIDTH as f32 / 2.0) * GRID_CELL_SIZE);
+    let text_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE);
+
+    // Cover the whole window so the board, hold and preview can't be studied while paused
+    spawn_screen_backdrop(&mut commands, &window_query, PauseOverlay {});
+
+    commands.spawn((
+        Text2d::new("Paused"),
+        TextColor(Color::srgb(0.8, 0.85, 0.9)),
+        TextFont {
             font: font.clone(),
             font_size: 100.0,
             ..default()
-        };
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(text_x, text_y + 100.0, 51.0),
+        PauseOverlay {}
+    ));
 
-        // Draw Lose text in center of grid 
-        let text_x = grid_config.start_x + ((GRID_WIDTH as f32 / 2.0) * GRID_CELL_SIZE);
-        let text_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE);
+    commands.spawn((
+        Text2d::new(format!(
+            "{} to resume\n{} to restart\nQ to quit",
+            key_bindings.describe(Action::Pause), key_bindings.describe(Action::Restart)
+        )),
+        TextColor(Color::srgb(0.8, 0.85, 0.9)),
+        TextFont {
+            font: font.clone(),
+            font_size: 30.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(text_x, text_y - 50.0, 51.0),
+        PauseOverlay {}
+    ));
+}
 
-        commands.spawn((
-            Text2d::new("You Lose"),
-            TextColor(Color::srgb(1.0, 0.2, 0.2)),
-            text_font.clone(),
-            TextLayout::new_with_justify(JustifyText::Center),
-            Transform::from_xyz(text_x, text_y, 0.0),
-            AnimateLoseText {}
-        ));
+pub fn despawn_pause_overlay(
+    mut commands: Commands,
+    pause_overlay_query: Query<Entity, With<PauseOverlay>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+){
+    virtual_time.unpause();
+    for entity in pause_overlay_query.iter() {
+        commands.entity(entity).despawn();
     }
 }
 
+// Game Over
+pub fn spawn_lose_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    grid_config: Res<GridConfig>,
+    game_result: Res<GameResult>,
+    game_mode: Res<GameMode>,
+){
+    let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+    let text_font = TextFont {
+        font: font.clone(),
+        font_size: 100.0,
+        ..default()
+    };
+    let (text, text_color) = match *game_result {
+        GameResult::Lost => ("You Lose", TextColor(Color::srgb(1.0, 0.2, 0.2))),
+        GameResult::Finished => (game_mode.finish_text(), TextColor(Color::srgb(0.3, 0.9, 0.4))),
+    };
+
+    // Draw the result in center of grid
+    let text_x = grid_config.start_x + ((GRID_WIDTH as f32 / 2.0) * GRID_CELL_SIZE);
+    let text_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE);
+
+    commands.spawn((
+        Text2d::new(text),
+        text_color,
+        text_font.clone(),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(text_x, text_y, 1.0),
+        AnimateLoseText {}
+    ));
+}
+
 pub fn animate_lose_text(
     mut lose_text_query: Query<&mut Transform, With<AnimateLoseText>>,
     time: Res<Time>
@@ -106,17 +381,143 @@ pub fn animate_lose_text(
         transform.scale.x = scale;
         transform.scale.y = scale;
     }
-} 
+}
+
+pub fn start_game_over_animation(
+    mut game_over_animation: ResMut<GameOverAnimation>
+){
+    game_over_animation.timer.reset();
+    game_over_animation.rows_greyed = 0;
+}
 
-pub fn reset_lose_text(
+pub fn animate_game_over(
+    time: Res<Time>,
+    mut game_over_animation: ResMut<GameOverAnimation>,
+    mut redraw_grid_event: EventWriter<RedrawGridEvent>,
+    mut next_state: ResMut<NextState<AppState>>,
+){
+    // Grey out the final stack one row at a time from the bottom, then show the results
+    game_over_animation.timer.tick(time.delta());
+    if !game_over_animation.timer.just_finished() {
+        return;
+    }
+
+    game_over_animation.rows_greyed += 1;
+    let rows_greyed = game_over_animation.rows_greyed;
+    if rows_greyed <= BOARD_TOTAL_HEIGHT {
+        redraw_grid_event.send(RedrawGridEvent);
+    } else if rows_greyed > BOARD_TOTAL_HEIGHT + GAME_OVER_HOLD_ROWS {
+        next_state.set(AppState::Results);
+    }
+}
+
+pub fn detect_dismiss_game_over(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+){
+    // Skip the animation
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        next_state.set(AppState::Results);
+    }
+}
+
+// Results
+pub fn spawn_results_screen(
     mut commands: Commands,
-    mut game_start_event: EventReader<GameStartEvent>,
-    lose_text_query: Query<(Entity, &mut Transform), With<AnimateLoseText>>,
+    asset_server: Res<AssetServer>,
+    grid_config: Res<GridConfig>,
+    game: Res<GameCore>,
+    game_result: Res<GameResult>,
+    game_mode: Res<GameMode>,
 ){
-    if !game_start_event.is_empty(){
-        game_start_event.clear();
-        for (entity, _) in lose_text_query.iter(){
-            commands.entity(entity).despawn();
-        }
+    let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+    let text_font = TextFont {
+        font: font.clone(),
+        font_size: 30.0,
+        ..default()
+    };
+    let text_color = TextColor(Color::srgb(0.8, 0.85, 0.9));
+
+    // Panel over the middle of the grid, the greyed out board stays visible around it
+    let panel_x = grid_config.start_x + ((GRID_WIDTH as f32 / 2.0) * GRID_CELL_SIZE);
+    let panel_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE);
+
+    commands.spawn((
+        Sprite::from_color(Color::srgba(0.05, 0.05, 0.1, 0.95), Vec2::new(520.0, 620.0)),
+        Transform::from_xyz(panel_x, panel_y, 50.0),
+        ResultsScreen {}
+    ));
+
+    commands.spawn((
+        Text2d::new(match *game_result {
+            GameResult::Lost => "Results",
+            GameResult::Finished => game_mode.finish_text(),
+        }),
+        text_color,
+        TextFont {
+            font: font.clone(),
+            font_size: 60.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(panel_x, panel_y + 240.0, 51.0),
+        ResultsScreen {}
+    ));
+
+    let (scoring_resource, game_stats) = (&game.scoring, &game.stats);
+    let results = vec![
+        ("Score", scoring_resource.score.to_string()),
+        ("Level", scoring_resource.level.to_string()),
+        ("Lines", scoring_resource.lines_cleared.to_string()),
+        ("Time", format_play_time(game_stats.play_time)),
+        ("Pieces", game_stats.pieces_placed.to_string()),
+        ("PPS", format!("{:.2}", game_stats.pieces_per_second())),
+        ("Max Combo", game_stats.max_combo.to_string()),
+        ("Tetrises", game_stats.tetrises.to_string()),
+        ("Seed", game.settings.seed.to_string()),
+    ];
+
+    let text_gap = 45.0;
+    let mut text_y = panel_y + 160.0;
+    for (label, value) in results {
+        commands.spawn((
+            Text2d::new(label),
+            text_color,
+            text_font.clone(),
+            TextLayout::new_with_justify(JustifyText::Left),
+            Transform::from_xyz(panel_x - 100.0, text_y, 51.0),
+            ResultsScreen {}
+        ));
+        commands.spawn((
+            Text2d::new(value),
+            text_color,
+            text_font.clone(),
+            TextLayout::new_with_justify(JustifyText::Right),
+            Transform::from_xyz(panel_x + 120.0, text_y, 51.0),
+            ResultsScreen {}
+        ));
+        text_y -= text_gap;
+    }
+
+    commands.spawn((
+        Text2d::new("Press ENTER"),
+        text_color,
+        TextFont {
+            font: font.clone(),
+            font_size: 20.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(panel_x, panel_y - 270.0, 51.0),
+        ResultsScreen {}
+    ));
+}
+
+pub fn detect_dismiss_results(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+){
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        next_state.set(AppState::Title);
     }
-}
\ No newline at end of file
+}
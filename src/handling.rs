@@ -0,0 +1,206 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use crate::data_file::{data_file_path, read_header, read_data_file, write_data_file};
+use crate::grid::{GRID_WIDTH, GRID_HEIGHT};
+use crate::play::{GameCore, tick_game};
+use crate::game_manager::AppState;
+use crate::controls::{Action, KeyBindings};
+
+pub struct HandlingPlugin;
+impl Plugin for HandlingPlugin{
+    fn build(&self, app: &mut App){
+        app
+            .insert_resource(load_handling_config())
+            .insert_resource(AutoShift::default())
+            .add_systems(OnExit(AppState::Menu), save_handling_config)
+            .add_systems(Update, update_auto_shift.before(tick_game).run_if(in_state(AppState::Playing)));
+    }
+}
+
+const HANDLING_FILE_VERSION: u32 = 1;
+const HANDLING_FILE_HEADER: &str = "tetris-handling";
+pub const MAX_DAS_MS: u32 = 500;
+pub const MAX_ARR_MS: u32 = 200;
+pub const MAX_SOFT_DROP_FACTOR: u32 = 40;
+
+// Resources
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct HandlingConfig {
+    pub das_ms: u32, // How long a direction has to be held before it starts repeating
+    pub arr_ms: u32, // Time between repeats once DAS is charged, 0 moves straight to the wall
+    pub soft_drop_factor: u32, // How many times faster than gravity soft drop is, at least 1
+}
+impl Default for HandlingConfig {
+    fn default() -> Self {
+        HandlingConfig {
+            das_ms: 167,
+            arr_ms: 33,
+            soft_drop_factor: 20,
+        }
+    }
+}
+impl HandlingConfig {
+    pub fn das_seconds(&self) -> f32 {
+        self.das_ms as f32 / 1000.0
+    }
+
+    pub fn arr_seconds(&self) -> f32 {
+        self.arr_ms as f32 / 1000.0
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct AutoShift {
+    pub direction: i32, // -1 left, 1 right, 0 when neither is held
+    pub das_charge: f32, // Kept between pieces so DAS stays charged through spawns
+    pub repeat_accumulator: f32,
+    pub soft_drop_accumulator: f32,
+    pub pending_shifts: i32, // Cells to move this frame, negative is left
+    pub pending_soft_drops: usize, // Rows to soft drop this frame
+}
+
+pub fn update_auto_shift(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    handling_config: Res<HandlingConfig>,
+    game: Res<GameCore>,
+    mut auto_shift: ResMut<AutoShift>,
+){
+    let delta = time.delta_secs();
+    let left_held = key_bindings.pressed(&keyboard_input, Action::MoveLeft);
+    let right_held = key_bindings.pressed(&keyboard_input, Action::MoveRight);
+    auto_shift.pending_shifts = 0;
+    auto_shift.pending_soft_drops = 0;
+
+    // Last pressed direction wins. Letting go of it falls back to the other one if it's still held
+    let mut new_direction = None;
+    if key_bindings.just_pressed(&keyboard_input, Action::MoveLeft) {
+        new_direction = Some(-1);
+    }
+    if key_bindings.just_pressed(&keyboard_input, Action::MoveRight) {
+        new_direction = Some(1);
+    }
+    if new_direction.is_none() {
+        if auto_shift.direction == -1 && !left_held {
+            new_direction = Some(if right_held { 1 } else { 0 });
+        } else if auto_shift.direction == 1 && !right_held {
+            new_direction = Some(if left_held { -1 } else { 0 });
+        }
+    }
+
+    if let Some(direction) = new_direction {
+        // A change of direction moves once straight away and starts charging DAS again
+        auto_shift.direction = direction;
+        auto_shift.das_charge = 0.0;
+        auto_shift.repeat_accumulator = 0.0;
+        auto_shift.pending_shifts = direction;
+    } else if auto_shift.direction != 0 {
+        let previous_charge = auto_shift.das_charge;
+        auto_shift.das_charge += delta;
+
+        let das_seconds = handling_config.das_seconds();
+        if auto_shift.das_charge >= das_seconds {
+            let mut shifts = 0;
+            if previous_charge < das_seconds {
+                // DAS just finished charging
+                shifts += 1;
+                auto_shift.repeat_accumulator = auto_shift.das_charge - das_seconds;
+            } else {
+                auto_shift.repeat_accumulator += delta;
+            }
+
+            let arr_seconds = handling_config.arr_seconds();
+            if arr_seconds <= 0.0 {
+                shifts = GRID_WIDTH as i32;
+            } else {
+                let repeats = (auto_shift.repeat_accumulator / arr_seconds).floor();
+                auto_shift.repeat_accumulator -= repeats * arr_seconds;
+                shifts += repeats as i32;
+            }
+            auto_shift.pending_shifts = shifts * auto_shift.direction;
+        }
+    }
+
+    // Soft drop moves a row as soon as it's pressed, then repeats at gravity sped up by the soft drop factor
+    if key_bindings.just_pressed(&keyboard_input, Action::SoftDrop) {
+        auto_shift.soft_drop_accumulator = 0.0;
+        auto_shift.pending_soft_drops = 1;
+    } else if key_bindings.pressed(&keyboard_input, Action::SoftDrop) {
+        let soft_drop_seconds = game.gravity.seconds_per_row() / handling_config.soft_drop_factor as f32;
+        if soft_drop_seconds <= 0.0 {
+            auto_shift.pending_soft_drops = GRID_HEIGHT;
+        } else {
+            auto_shift.soft_drop_accumulator += delta;
+            let rows = (auto_shift.soft_drop_accumulator / soft_drop_seconds).floor();
+            auto_shift.soft_drop_accumulator -= rows * soft_drop_seconds;
+            auto_shift.pending_soft_drops = rows as usize;
+        }
+    }
+}
+
+// Saving and loading
+fn handling_path() -> Option<PathBuf> {
+    data_file_path("handling.txt")
+}
+
+pub fn parse_handling_config(contents: &str) -> Result<HandlingConfig, String> {
+    // Same layout as the controls file, one setting=value line each. Missing or unreadable settings keep
+    // their defaults, values out of range are brought back into it
+    let lines = read_header(contents, HANDLING_FILE_HEADER, HANDLING_FILE_VERSION)?;
+
+    let mut handling_config = HandlingConfig::default();
+    for line in lines {
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        let Ok(value) = value.trim().parse::<u32>() else {
+            continue;
+        };
+        match name.trim() {
+            "das_ms" => handling_config.das_ms = value.min(MAX_DAS_MS),
+            "arr_ms" => handling_config.arr_ms = value.min(MAX_ARR_MS),
+            "soft_drop_factor" => handling_config.soft_drop_factor = value.clamp(1, MAX_SOFT_DROP_FACTOR),
+            _ => {}
+        }
+    }
+    Ok(handling_config)
+}
+
+pub fn serialize_handling_config(handling_config: &HandlingConfig) -> String {
+    format!(
+        "{} {}\ndas_ms={}\narr_ms={}\nsoft_drop_factor={}\n",
+        HANDLING_FILE_HEADER, HANDLING_FILE_VERSION, handling_config.das_ms, handling_config.arr_ms, handling_config.soft_drop_factor
+    )
+}
+
+pub fn load_handling_config() -> HandlingConfig {
+    let Some(path) = handling_path() else {
+        warn!("No data directory found, handling settings won't be saved");
+        return HandlingConfig::default();
+    };
+    read_data_file(&path, parse_handling_config).unwrap_or_default()
+}
+
+pub fn save_handling_config(
+    handling_config: Res<HandlingConfig>
+){
+    if let Some(path) = handling_path() {
+        write_data_file(&path, &serialize_handling_config(&handling_config));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handling_survives_a_save_and_stays_in_range() {
+        let handling_config = HandlingConfig { das_ms: 100, arr_ms: 0, soft_drop_factor: 40 };
+        assert_eq!(parse_handling_config(&serialize_handling_config(&handling_config)), Ok(handling_config));
+
+        let loaded = parse_handling_config("tetris-handling 1\ndas_ms=9000\narr_ms=fast\nsoft_drop_factor=0\n").unwrap();
+        assert_eq!(loaded, HandlingConfig { das_ms: MAX_DAS_MS, arr_ms: 33, soft_drop_factor: 1 });
+    }
+}
\ No newline at end of file
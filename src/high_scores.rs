@@ -0,0 +1,434 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use crate::game_manager::{AppState, GameResult, TitleScreen, ResultsScreen, detect_dismiss_results};
+use crate::grid::{GridConfig, GRID_CELL_SIZE, GRID_HEIGHT};
+use crate::mode::{GameMode, SprintProgress};
+use crate::core::Game;
+use crate::data_file::{data_file_path, read_header, read_data_file, write_data_file};
+use crate::play::GameCore;
+use crate::scoring::format_play_time;
+
+pub struct HighScoresPlugin;
+impl Plugin for HighScoresPlugin{
+    fn build(&self, app: &mut App){
+        app
+            // Loaded here rather than in a startup system, the first title screen is drawn before Startup runs
+            .insert_resource(load_high_scores())
+            .insert_resource(NameEntry { name: String::new(), active: false })
+            .add_systems(OnEnter(AppState::Title), draw_title_high_scores)
+            .add_systems(OnEnter(AppState::Results), start_name_entry)
+            .add_systems(Update, detect_name_entry_input.after(detect_dismiss_results))
+            .add_systems(Update, draw_results_high_scores.after(detect_name_entry_input).run_if(in_state(AppState::Results)));
+    }
+}
+
+pub const HIGH_SCORE_FILE_VERSION: u32 = 1;
+const HIGH_SCORE_FILE_HEADER: &str = "tetris-high-scores";
+pub const HIGH_SCORES_PER_MODE: usize = 10;
+const MAX_NAME_LENGTH: usize = 12;
+
+#[derive(Clone, Debug)]
+pub struct HighScoreEntry {
+    pub mode: String, // GameMode::key
+    pub name: String,
+    pub score: usize,
+    pub lines: usize,
+    pub level: usize,
+    pub duration: Duration,
+    pub date: u64, // Seconds since the unix epoch
+    pub splits: Vec<Duration>, // Sprint split times, empty for other modes
+    pub unknown_lines: Vec<String>, // Lines from the file this version doesn't understand, saved back as they were
+}
+
+#[derive(Resource, Default)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>, // Every mode, each one sorted best first
+    pub unreadable: Vec<Vec<String>>, // Entries from the file that couldn't be read, saved back untouched so nothing is lost
+}
+impl HighScores {
+    pub fn for_mode(&self, mode: &GameMode) -> Vec<&HighScoreEntry> {
+        let key = mode.key();
+        self.entries.iter().filter(|entry| entry.mode == key).collect()
+    }
+
+    pub fn qualifies(&self, mode: &GameMode, candidate: &HighScoreEntry) -> bool {
+        let table = self.for_mode(mode);
+        table.len() < HIGH_SCORES_PER_MODE || table.iter().any(|entry| mode.compare_entries(candidate, entry).is_lt())
+    }
+
+    pub fn insert(&mut self, mode: &GameMode, entry: HighScoreEntry) {
+        // Other modes' tables are left alone, only the top of this one is kept
+        let key = mode.key();
+        let (mut table, others): (Vec<HighScoreEntry>, Vec<HighScoreEntry>) = self.entries
+            .drain(..)
+            .chain(std::iter::once(entry))
+            .partition(|entry| entry.mode == key);
+        table.sort_by(|a, b| mode.compare_entries(a, b));
+        table.truncate(HIGH_SCORES_PER_MODE);
+        self.entries = others;
+        self.entries.extend(table);
+    }
+}
+
+#[derive(Resource)]
+pub struct NameEntry {
+    pub name: String,
+    pub active: bool,
+}
+
+#[derive(Component)]
+pub struct HighScoreTableText;
+
+#[derive(Component)]
+pub struct NameEntryText;
+
+type ResultsTextFilter = Or<(With<HighScoreTableText>, With<NameEntryText>)>;
+
+// Run condition so Enter confirms the name instead of leaving the results screen
+pub fn is_entering_name(name_entry: Res<NameEntry>) -> bool {
+    name_entry.active
+}
+
+// Saving and loading
+fn high_scores_path() -> Option<PathBuf> {
+    data_file_path("high_scores.txt")
+}
+
+pub fn parse_high_scores(contents: &str) -> Result<HighScores, String> {
+    // The first line is the header and version. Every entry starts with [entry] followed by key=value lines.
+    // Unknown keys are kept aside so files written by newer versions still load, entries missing a field are kept
+    // as unreadable. Both are written back on the next save
+    let lines = read_header(contents, HIGH_SCORE_FILE_HEADER, HIGH_SCORE_FILE_VERSION)?;
+
+    let mut high_scores = HighScores::default();
+    let mut block: Vec<String> = vec![];
+    for line in lines.chain(std::iter::once("[entry]")) {
+        let line = line.trim();
+        if line == "[entry]" {
+            if !block.is_empty() {
+                match entry_from_lines(&block) {
+                    Some(entry) => high_scores.entries.push(entry),
+                    None => {
+                        warn!("Couldn't read a high score entry, keeping it as it is: {:?}", block);
+                        high_scores.unreadable.push(block.clone());
+                    }
+                }
+                block.clear();
+            }
+        } else if !line.is_empty() {
+            block.push(line.to_string());
+        }
+    }
+    Ok(high_scores)
+}
+
+const ENTRY_KEYS: [&str; 8] = ["mode", "name", "score", "lines", "level", "duration_ms", "date", "splits"];
+
+fn entry_from_lines(lines: &[String]) -> Option<HighScoreEntry> {
+    let fields: Vec<(String, String)> = lines.iter()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let unknown_lines = lines.iter()
+        .filter(|line| line.split_once('=').is_none_or(|(key, _)| !ENTRY_KEYS.contains(&key.trim())))
+        .cloned()
+        .collect();
+    let field = |key: &str| fields.iter().find(|(field_key, _)| field_key == key).map(|(_, value)| value.as_str());
+    Some(HighScoreEntry {
+        mode: field("mode")?.to_string(),
+        name: field("name")?.to_string(),
+        score: field("score")?.parse().ok()?,
+        lines: field("lines")?.parse().ok()?,
+        level: field("level")?.parse().ok()?,
+        duration: Duration::from_millis(field("duration_ms")?.parse().ok()?),
+        date: field("date")?.parse().ok()?,
+        // Added after the first release, so older entries don't have it
+        splits: field("splits").map_or(vec![], |splits| {
+            splits.split(',').filter_map(|split| split.parse().ok()).map(Duration::from_millis).collect()
+        }),
+        unknown_lines,
+    })
+}
+
+pub fn serialize_high_scores(high_scores: &HighScores) -> String {
+    let mut contents = format!("{} {}\n", HIGH_SCORE_FILE_HEADER, HIGH_SCORE_FILE_VERSION);
+    for entry in &high_scores.entries {
+        contents.push_str("[entry]\n");
+        contents.push_str(&format!("mode={}\n", entry.mode));
+        contents.push_str(&format!("name={}\n", entry.name));
+        contents.push_str(&format!("score={}\n", entry.score));
+        contents.push_str(&format!("lines={}\n", entry.lines));
+        contents.push_str(&format!("level={}\n", entry.level));
+        contents.push_str(&format!("duration_ms={}\n", entry.duration.as_millis()));
+        contents.push_str(&format!("date={}\n", entry.date));
+        if !entry.splits.is_empty() {
+            let splits: Vec<String> = entry.splits.iter().map(|split| split.as_millis().to_string()).collect();
+            contents.push_str(&format!("splits={}\n", splits.join(",")));
+        }
+        for line in &entry.unknown_lines {
+            contents.push_str(&format!("{}\n", line));
+        }
+    }
+    for lines in &high_scores.unreadable {
+        contents.push_str("[entry]\n");
+        for line in lines {
+            contents.push_str(&format!("{}\n", line));
+        }
+    }
+    contents
+}
+
+pub fn load_high_scores() -> HighScores {
+    let Some(path) = high_scores_path() else {
+        warn!("No data directory found, high scores won't be saved");
+        return HighScores::default();
+    };
+    read_data_file(&path, parse_high_scores).unwrap_or_default()
+}
+
+pub fn save_high_scores(high_scores: &HighScores) {
+    if let Some(path) = high_scores_path() {
+        write_data_file(&path, &serialize_high_scores(high_scores));
+    }
+}
+
+pub fn format_date(date: u64) -> String {
+    // Days since the epoch to a civil yyyy-mm-dd date
+    let days = (date / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Drawing
+fn spawn_high_score_table(
+    commands: &mut Commands,
+    text_font: &TextFont,
+    high_scores: &HighScores,
+    game_mode: &GameMode,
+    position: Vec3,
+    marker: impl Component,
+){
+    let text_color = TextColor(Color::srgb(0.8, 0.85, 0.9));
+    let table = high_scores.for_mode(game_mode);
+    let mut text = format!("{} High Scores\n", game_mode.name());
+    if table.is_empty() {
+        text.push_str("\nNo scores yet");
+    }
+    for (rank, entry) in table.iter().enumerate() {
+        text.push_str(&format!(
+            "\n{:>2}. {:<12} {:>8}  L{:<3} {:>4} lines  {}  {}",
+            rank + 1, entry.name, entry.score, entry.level, entry.lines, format_play_time(entry.duration), format_date(entry.date)
+        ));
+    }
+
+    commands.spawn((
+        Text2d::new(text),
+        text_color,
+        text_font.clone(),
+        TextLayout::new_with_justify(JustifyText::Left),
+        Transform::from_translation(position),
+        HighScoreTableText {},
+        marker
+    ));
+}
+
+pub fn draw_title_high_scores(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    high_scores: Res<HighScores>,
+    game_mode: Res<GameMode>,
+){
+    let text_font = TextFont {
+        font: asset_server.load("fonts/gg-sans-Regular.ttf"),
+        font_size: 20.0,
+        ..default()
+    };
+    spawn_high_score_table(&mut commands, &text_font, &high_scores, &game_mode, Vec3::new(0.0, -250.0, 51.0), TitleScreen {});
+}
+
+fn current_entry(
+    name: String,
+    game_mode: &GameMode,
+    game: &Game,
+    sprint_progress: &SprintProgress,
+) -> HighScoreEntry {
+    HighScoreEntry {
+        mode: game_mode.key(),
+        name,
+        score: game.scoring.score,
+        lines: game.scoring.lines_cleared,
+        level: game.scoring.level,
+        duration: game.stats.play_time,
+        date: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since_epoch| since_epoch.as_secs()),
+        splits: sprint_progress.splits.clone(),
+        unknown_lines: vec![],
+    }
+}
+
+pub fn start_name_entry(
+    high_scores: Res<HighScores>,
+    game_mode: Res<GameMode>,
+    game_result: Res<GameResult>,
+    game: Res<GameCore>,
+    sprint_progress: Res<SprintProgress>,
+    mut name_entry: ResMut<NameEntry>,
+){
+    // Only ask for a name when the game makes it onto the table
+    name_entry.name.clear();
+    let candidate = current_entry(String::new(), &game_mode, &game, &sprint_progress);
+    name_entry.active = game_mode.is_rankable(*game_result, game.scoring.score) && high_scores.qualifies(&game_mode, &candidate);
+}
+
+pub fn detect_name_entry_input(
+    mut keyboard_input_event: EventReader<KeyboardInput>,
+    mut name_entry: ResMut<NameEntry>,
+    mut high_scores: ResMut<HighScores>,
+    game_mode: Res<GameMode>,
+    game: Res<GameCore>,
+    sprint_progress: Res<SprintProgress>,
+){
+    // Always runs so keys pressed before the results screen opened aren't typed into the name
+    if !name_entry.active {
+        keyboard_input_event.clear();
+        return;
+    }
+
+    for event in keyboard_input_event.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Enter => {
+                let name = name_entry.name.trim();
+                let name = if name.is_empty() { "Player".to_string() } else { name.to_string() };
+                high_scores.insert(&game_mode, current_entry(name, &game_mode, &game, &sprint_progress));
+                save_high_scores(&high_scores);
+                name_entry.active = false;
+                return;
+            }
+            Key::Backspace => {
+                name_entry.name.pop();
+            }
+            Key::Character(characters) => {
+                // Letters, digits and spaces keep the save file format simple
+                for character in characters.chars() {
+                    if (character.is_alphanumeric() || character == ' ') && name_entry.name.chars().count() < MAX_NAME_LENGTH {
+                        name_entry.name.push(character);
+                    }
+                }
+            }
+            Key::Space if name_entry.name.chars().count() < MAX_NAME_LENGTH => {
+                name_entry.name.push(' ');
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn draw_results_high_scores(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    grid_config: Res<GridConfig>,
+    high_scores: Res<HighScores>,
+    game_mode: Res<GameMode>,
+    name_entry: Res<NameEntry>,
+    results_text_query: Query<Entity, ResultsTextFilter>,
+){
+    if !name_entry.is_changed() {
+        return;
+    }
+    for entity in results_text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let text_font = TextFont {
+        font: asset_server.load("fonts/gg-sans-Regular.ttf"),
+        font_size: 18.0,
+        ..default()
+    };
+
+    // Left of the grid, over the tips
+    let text_x = grid_config.start_x - 400.0;
+    let text_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE);
+
+    commands.spawn((
+        Sprite::from_color(Color::srgba(0.05, 0.05, 0.1, 0.95), Vec2::new(640.0, 500.0)),
+        Transform::from_xyz(text_x, text_y, 50.0),
+        ResultsScreen {},
+        NameEntryText {}
+    ));
+
+    if name_entry.active {
+        commands.spawn((
+            Text2d::new(format!("New High Score!\nName: {}_\n\nENTER to save", name_entry.name)),
+            TextColor(Color::srgb(1.0, 0.85, 0.2)),
+            TextFont {
+                font_size: 30.0,
+                ..text_font.clone()
+            },
+            TextLayout::new_with_justify(JustifyText::Center),
+            Transform::from_xyz(text_x, text_y, 51.0),
+            ResultsScreen {},
+            NameEntryText {}
+        ));
+    } else {
+        spawn_high_score_table(&mut commands, &text_font, &high_scores, &game_mode, Vec3::new(text_x, text_y, 51.0), ResultsScreen {});
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_that_cant_be_read_survive_a_save() {
+        let contents = "tetris-high-scores 1\n\
+            [entry]\nmode=marathon\nname=AAA\nscore=100\nlines=4\nlevel=1\nduration_ms=60000\ndate=0\ncolour=blue\n\
+            [entry]\nmode=sprint\nname=BBB\nscore=not a number\n";
+        let high_scores = parse_high_scores(contents).unwrap();
+        assert_eq!(high_scores.entries.len(), 1);
+        assert_eq!(high_scores.entries[0].unknown_lines, vec!["colour=blue".to_string()]);
+        assert_eq!(high_scores.unreadable.len(), 1);
+
+        let saved = serialize_high_scores(&high_scores);
+        assert!(saved.contains("colour=blue\n"));
+        assert!(saved.contains("[entry]\nmode=sprint\nname=BBB\nscore=not a number\n"));
+
+        // Loading the saved file again gives the same thing back
+        let reloaded = parse_high_scores(&saved).unwrap();
+        assert_eq!(serialize_high_scores(&reloaded), saved);
+    }
+
+    #[test]
+    fn first_title_screen_shows_saved_scores() {
+        let path = std::env::temp_dir().join(format!("tetris_high_scores_{}.txt", std::process::id()));
+        std::fs::write(&path, "tetris-high-scores 1\n\
+            [entry]\nmode=marathon\nname=AAA\nscore=1234\nlines=4\nlevel=1\nduration_ms=60000\ndate=0\n").unwrap();
+
+        // Same order as the game: scores are in place when the plugins are built, the title is entered before Startup
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), bevy::state::app::StatesPlugin))
+            .init_asset::<Font>()
+            .init_state::<AppState>()
+            .insert_resource(GameMode::Marathon { goal: crate::mode::MarathonGoal::Endless })
+            .insert_resource(read_data_file(&path, parse_high_scores).unwrap())
+            .add_systems(OnEnter(AppState::Title), draw_title_high_scores);
+        app.update();
+        let _ = std::fs::remove_file(&path);
+
+        let mut tables = app.world_mut().query_filtered::<&Text2d, With<HighScoreTableText>>();
+        let table = tables.single(app.world());
+        assert!(table.0.contains("AAA"), "{}", table.0);
+        assert!(table.0.contains("1234"), "{}", table.0);
+    }
+}
\ No newline at end of file
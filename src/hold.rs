@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+use crate::core::Piece;
+use crate::game_manager::AppState;
+use crate::grid::{GridConfig, CELL_BORDER_WIDTH, GRID_CELL_SIZE, GRID_HEIGHT};
+use crate::play::{GameCore, tick_game};
+use crate::tetromino::TetrominoColor;
+
+pub struct HoldPlugin;
+impl Plugin for HoldPlugin{
+    fn build(&self, app: &mut App){
+        app
+            .add_event::<RedrawHeldPieceEvent>()
+            .add_systems(OnEnter(AppState::Countdown), reset_held_piece)
+            .add_systems(OnExit(AppState::Countdown), draw_held_piece_text)
+            .add_systems(Update, draw_held_piece.after(tick_game));
+    }
+}
+
+// Components
+#[derive(Component)]
+pub struct HeldPieceCells;
+
+#[derive(Component)]
+pub struct HeldPieceText;
+
+type HoldPanelFilter = Or<(With<HeldPieceCells>, With<HeldPieceText>)>;
+
+// Events
+#[derive(Event)]
+pub struct RedrawHeldPieceEvent;
+
+pub fn draw_held_piece_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    grid_config: Res<GridConfig>,
+){
+    let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+    let text_font = TextFont {
+        font: font.clone(),
+        font_size: 25.0,
+        ..default()
+    };
+    let text_color = TextColor(Color::srgb(0.8, 0.85, 0.9));
+
+    let text_x = grid_config.start_x - 150.0;
+    let text_y = grid_config.start_y + (GRID_HEIGHT as f32 * GRID_CELL_SIZE) - 25.0;
+
+    commands.spawn((
+        Text2d::new("Hold"),
+        text_color,
+        text_font.clone(),
+        TextLayout::new_with_justify(JustifyText::Left),
+        Transform::from_xyz(text_x, text_y, 0.0),
+        HeldPieceText {}
+    ));
+}
+
+pub fn draw_held_piece(
+    mut commands: Commands,
+    game: Res<GameCore>,
+    held_piece_cells_query: Query<Entity, With<HeldPieceCells>>,
+    mut redraw_held_piece_event: EventReader<RedrawHeldPieceEvent>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    grid_config: Res<GridConfig>,
+){
+    if !redraw_held_piece_event.is_empty() {
+        redraw_held_piece_event.clear();
+
+        for entity in held_piece_cells_query.iter() {
+            commands.entity(entity).despawn();
+        }
+
+        if let Some(letter) = game.held {
+            let held_tetromino = Piece::new(letter, game.rotation_system.as_ref());
+            let color = TetrominoColor::for_letter(letter).to_color();
+            let initial_x = grid_config.start_x - 200.0;
+            let initial_y = grid_config.start_y + (GRID_HEIGHT as f32 * GRID_CELL_SIZE) - 100.0;
+            for y in 0..4 {
+                for x in 0..4 {
+                    if held_tetromino.shape[y][x] {
+                        let cell_x = initial_x + (x as f32 * GRID_CELL_SIZE);
+                        let cell_y = initial_y - (y as f32 * GRID_CELL_SIZE);
+
+                        // Draw the held piece
+                        commands.spawn((
+                            Mesh2d(meshes.add(Rectangle::default())),
+                            MeshMaterial2d(materials.add(color)),
+                            Transform::from_xyz(cell_x, cell_y, 0.0)
+                                .with_scale(Vec3::new(GRID_CELL_SIZE - CELL_BORDER_WIDTH, GRID_CELL_SIZE - CELL_BORDER_WIDTH, 1.0)),
+                            HeldPieceCells {}
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn reset_held_piece(
+    mut commands: Commands,
+    held_piece_text_query: Query<Entity, HoldPanelFilter>,
+){
+    // Remove the hold panel, the new game starts with nothing held
+    for entity in held_piece_text_query.iter(){
+        commands.entity(entity).despawn();
+    }
+}
\ No newline at end of file
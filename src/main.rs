@@ -5,13 +5,29 @@ use crate::game_manager::GameManagerPlugin;
 use crate::queue::QueuePlugin;
 use crate::scoring::ScoringPlugin;
 use crate::tips::TipsPlugin;
+use crate::hold::HoldPlugin;
+use crate::handling::HandlingPlugin;
+use crate::menu::MenuPlugin;
+use crate::mode::ModePlugin;
+use crate::high_scores::HighScoresPlugin;
+use crate::play::PlayPlugin;
+use crate::controls::ControlsPlugin;
 
+mod core;
+mod play;
 mod grid;
 mod tetromino;
 mod queue;
 mod game_manager;
 mod scoring;
 mod tips;
+mod hold;
+mod handling;
+mod menu;
+mod mode;
+mod high_scores;
+mod controls;
+mod data_file;
 
 fn main() {
     App::new()
@@ -30,7 +46,14 @@ fn main() {
                 GameManagerPlugin,
                 QueuePlugin,
                 ScoringPlugin,
-                TipsPlugin
+                TipsPlugin,
+                HoldPlugin,
+                HandlingPlugin,
+                MenuPlugin,
+                ModePlugin,
+                HighScoresPlugin,
+                PlayPlugin,
+                ControlsPlugin
         ))
         .add_systems(Startup, setup)
         .run();
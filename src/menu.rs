@@ -0,0 +1,295 @@
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use crate::game_manager::{AppState, spawn_screen_backdrop, despawn_screen};
+use crate::core::MAX_START_LEVEL;
+use crate::handling::{HandlingConfig, MAX_DAS_MS, MAX_ARR_MS, MAX_SOFT_DROP_FACTOR};
+use crate::mode::{GameMode, ModeRandomizers};
+use crate::play::{GameCore, RuleSettings, SeedConfig};
+use crate::queue::PreviewConfig;
+
+pub struct MenuPlugin;
+impl Plugin for MenuPlugin{
+    fn build(&self, app: &mut App){
+        app
+            .insert_resource(MenuSelection { row: 0 })
+            .add_event::<RedrawMenuEvent>()
+            .add_systems(OnEnter(AppState::Menu), spawn_menu_screen)
+            .add_systems(OnExit(AppState::Menu), (despawn_screen::<MenuScreen>, despawn_screen::<MenuRowText>))
+            .add_systems(Update, (detect_menu_input, draw_menu_rows).chain().run_if(in_state(AppState::Menu)));
+    }
+}
+
+// Every setting that can be changed before a game starts
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MenuRow {
+    Mode,
+    Randomizer,
+    StartLevel,
+    LevelUp,
+    Gravity,
+    LockDown,
+    Rotation,
+    Preview,
+    Das,
+    Arr,
+    SoftDropFactor,
+    Seed,
+}
+const MENU_ROWS: [MenuRow; 12] = [
+    MenuRow::Mode, MenuRow::Randomizer, MenuRow::StartLevel, MenuRow::LevelUp, MenuRow::Gravity, MenuRow::LockDown,
+    MenuRow::Rotation, MenuRow::Preview, MenuRow::Das, MenuRow::Arr, MenuRow::SoftDropFactor, MenuRow::Seed,
+];
+const DAS_STEP_MS: i32 = 10;
+const ARR_STEP_MS: i32 = 5;
+
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+    KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+];
+
+#[derive(SystemParam)]
+pub struct MenuSettings<'w> {
+    game_mode: ResMut<'w, GameMode>,
+    mode_randomizers: ResMut<'w, ModeRandomizers>,
+    rule_settings: ResMut<'w, RuleSettings>,
+    preview_config: ResMut<'w, PreviewConfig>,
+    seed_config: ResMut<'w, SeedConfig>,
+    handling_config: ResMut<'w, HandlingConfig>,
+    game: Res<'w, GameCore>,
+}
+impl MenuSettings<'_> {
+    fn label(&self, row: MenuRow) -> String {
+        match row {
+            MenuRow::Mode => format!("Mode: {}", self.game_mode.name()),
+            MenuRow::Randomizer => format!("Randomizer: {}", self.mode_randomizers.for_mode(&self.game_mode).name()),
+            MenuRow::StartLevel => format!("Start Level: {}", self.rule_settings.start_level),
+            MenuRow::LevelUp => format!("Level Up: {}", self.rule_settings.level_up_rule.name()),
+            MenuRow::Gravity => format!("Gravity: {}", self.rule_settings.gravity_curve.name()),
+            MenuRow::LockDown => format!("Lock Down: {}", self.rule_settings.lock_down_mode.name()),
+            MenuRow::Rotation => format!("Rotation: {}", self.rule_settings.rotation_system.name()),
+            MenuRow::Preview => format!("Next Pieces: {}", self.preview_config.count),
+            MenuRow::Das => format!("DAS: {} ms", self.handling_config.das_ms),
+            MenuRow::Arr => match self.handling_config.arr_ms {
+                0 => "ARR: 0 ms (instant)".to_string(),
+                arr_ms => format!("ARR: {} ms", arr_ms),
+            },
+            MenuRow::SoftDropFactor => format!("Soft Drop Speed: {}x", self.handling_config.soft_drop_factor),
+            MenuRow::Seed => match self.seed_config.seed {
+                Some(seed) => format!("Seed: {}", seed),
+                None => "Seed: Random".to_string(),
+            },
+        }
+    }
+
+    fn change(&mut self, row: MenuRow, direction: i32) {
+        match row {
+            MenuRow::Mode => {
+                *self.game_mode = if direction > 0 { self.game_mode.next() } else { self.game_mode.previous() };
+            }
+            MenuRow::Randomizer => {
+                // Only changes the randomizer for the mode that's picked
+                let randomizer = self.mode_randomizers.for_mode(&self.game_mode);
+                let randomizer = if direction > 0 { randomizer.next() } else { randomizer.previous() };
+                self.mode_randomizers.0.insert(*self.game_mode, randomizer);
+            }
+            MenuRow::StartLevel => {
+                let level = self.rule_settings.start_level as i32 + direction;
+                self.rule_settings.start_level = level.clamp(1, MAX_START_LEVEL as i32) as usize;
+            }
+            MenuRow::LevelUp => {
+                self.rule_settings.level_up_rule = if direction > 0 { self.rule_settings.level_up_rule.next() } else { self.rule_settings.level_up_rule.previous() };
+            }
+            MenuRow::Gravity => {
+                self.rule_settings.gravity_curve = if direction > 0 { self.rule_settings.gravity_curve.next() } else { self.rule_settings.gravity_curve.previous() };
+            }
+            MenuRow::LockDown => {
+                self.rule_settings.lock_down_mode = if direction > 0 { self.rule_settings.lock_down_mode.next() } else { self.rule_settings.lock_down_mode.previous() };
+            }
+            MenuRow::Rotation => {
+                self.rule_settings.rotation_system = if direction > 0 { self.rule_settings.rotation_system.next() } else { self.rule_settings.rotation_system.previous() };
+            }
+            MenuRow::Preview => {
+                let count = self.preview_config.count as i32 + direction;
+                *self.preview_config = PreviewConfig::new(count.max(0) as usize);
+            }
+            MenuRow::Das => {
+                let das_ms = self.handling_config.das_ms as i32 + direction * DAS_STEP_MS;
+                self.handling_config.das_ms = das_ms.clamp(0, MAX_DAS_MS as i32) as u32;
+            }
+            MenuRow::Arr => {
+                let arr_ms = self.handling_config.arr_ms as i32 + direction * ARR_STEP_MS;
+                self.handling_config.arr_ms = arr_ms.clamp(0, MAX_ARR_MS as i32) as u32;
+            }
+            MenuRow::SoftDropFactor => {
+                let factor = self.handling_config.soft_drop_factor as i32 + direction;
+                self.handling_config.soft_drop_factor = factor.clamp(1, MAX_SOFT_DROP_FACTOR as i32) as u32;
+            }
+            MenuRow::Seed => {
+                // Switch between a new seed every game and replaying the last game's seed
+                self.seed_config.seed = match self.seed_config.seed {
+                    Some(_) => None,
+                    None => Some(self.game.settings.seed),
+                };
+            }
+        }
+    }
+
+    fn type_seed_digit(&mut self, digit: u64) {
+        let seed = self.seed_config.seed.unwrap_or(0);
+        self.seed_config.seed = Some(seed.checked_mul(10).and_then(|seed| seed.checked_add(digit)).unwrap_or(seed));
+    }
+
+    fn erase_seed_digit(&mut self) {
+        // Erasing the last digit goes back to a random seed
+        self.seed_config.seed = self.seed_config.seed.map(|seed| seed / 10).filter(|seed| *seed > 0);
+    }
+}
+
+#[derive(Resource)]
+pub struct MenuSelection {
+    pub row: usize,
+}
+
+#[derive(Component)]
+pub struct MenuScreen;
+
+#[derive(Component)]
+pub struct MenuRowText;
+
+#[derive(Event)]
+pub struct RedrawMenuEvent;
+
+pub fn spawn_menu_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    window_query: Query<&Window>,
+    mut redraw_menu_event: EventWriter<RedrawMenuEvent>,
+){
+    let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+    spawn_screen_backdrop(&mut commands, &window_query, MenuScreen {});
+
+    commands.spawn((
+        Text2d::new("Settings"),
+        TextColor(Color::srgb(0.8, 0.85, 0.9)),
+        TextFont {
+            font: font.clone(),
+            font_size: 80.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(0.0, 300.0, 51.0),
+        MenuScreen {}
+    ));
+
+    commands.spawn((
+        Text2d::new("Up/Down to choose, Left/Right to change, type a number for the seed\nENTER to start, ESC to go back"),
+        TextColor(Color::srgb(0.8, 0.85, 0.9)),
+        TextFont {
+            font: font.clone(),
+            font_size: 20.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(0.0, -350.0, 51.0),
+        MenuScreen {}
+    ));
+
+    redraw_menu_event.send(RedrawMenuEvent);
+}
+
+pub fn detect_menu_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut menu_selection: ResMut<MenuSelection>,
+    mut menu_settings: MenuSettings,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut redraw_menu_event: EventWriter<RedrawMenuEvent>,
+){
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        next_state.set(AppState::Countdown);
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::Title);
+        return;
+    }
+
+    // Choose a row
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        menu_selection.row = (menu_selection.row + MENU_ROWS.len() - 1) % MENU_ROWS.len();
+        redraw_menu_event.send(RedrawMenuEvent);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        menu_selection.row = (menu_selection.row + 1) % MENU_ROWS.len();
+        redraw_menu_event.send(RedrawMenuEvent);
+    }
+
+    // Change the chosen setting
+    let row = MENU_ROWS[menu_selection.row];
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        menu_settings.change(row, -1);
+        redraw_menu_event.send(RedrawMenuEvent);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        menu_settings.change(row, 1);
+        redraw_menu_event.send(RedrawMenuEvent);
+    }
+
+    // The seed can also be typed in
+    if row == MenuRow::Seed {
+        for (digit, key) in DIGIT_KEYS.iter().enumerate() {
+            if keyboard_input.just_pressed(*key) {
+                menu_settings.type_seed_digit(digit as u64);
+                redraw_menu_event.send(RedrawMenuEvent);
+            }
+        }
+        if keyboard_input.just_pressed(KeyCode::Backspace) {
+            menu_settings.erase_seed_digit();
+            redraw_menu_event.send(RedrawMenuEvent);
+        }
+    }
+}
+
+pub fn draw_menu_rows(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    menu_selection: Res<MenuSelection>,
+    menu_settings: MenuSettings,
+    menu_row_text_query: Query<Entity, With<MenuRowText>>,
+    mut redraw_menu_event: EventReader<RedrawMenuEvent>,
+){
+    if !redraw_menu_event.is_empty() {
+        redraw_menu_event.clear();
+
+        for entity in menu_row_text_query.iter() {
+            commands.entity(entity).despawn();
+        }
+
+        let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+        let text_font = TextFont {
+            font: font.clone(),
+            font_size: 30.0,
+            ..default()
+        };
+        let text_gap = 42.0;
+        let mut text_y = 230.0;
+
+        for (index, row) in MENU_ROWS.iter().enumerate() {
+            let (text, text_color) = if index == menu_selection.row {
+                (format!("< {} >", menu_settings.label(*row)), TextColor(Color::srgb(1.0, 0.85, 0.2)))
+            } else {
+                (menu_settings.label(*row), TextColor(Color::srgb(0.8, 0.85, 0.9)))
+            };
+
+            commands.spawn((
+                Text2d::new(text),
+                text_color,
+                text_font.clone(),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Transform::from_xyz(0.0, text_y, 51.0),
+                MenuRowText {}
+            ));
+
+            text_y -= text_gap;
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use crate::core::{ModeGoal, RandomizerKind};
+use crate::game_manager::{AppState, GameResult, ResultsScreen};
+use crate::grid::{GridConfig, GRID_WIDTH, GRID_CELL_SIZE, GRID_HEIGHT};
+use crate::high_scores::{HighScores, HighScoreEntry};
+use crate::play::{GameCore, new_game, tick_game};
+use crate::scoring::format_play_time;
+
+pub struct ModePlugin;
+impl Plugin for ModePlugin{
+    fn build(&self, app: &mut App){
+        app
+            .insert_resource(GameMode::Marathon { goal: MarathonGoal::Endless })
+            .insert_resource(SprintProgress { splits: vec![] })
+            .insert_resource(ModeRandomizers::default())
+            .add_systems(OnEnter(AppState::Countdown), (reset_sprint_progress, despawn_mode_text, draw_mode_text).chain().after(new_game))
+            .add_systems(OnEnter(AppState::Results), draw_sprint_results)
+            .add_systems(Update, (update_sprint_progress, draw_mode_text).chain().after(tick_game).run_if(in_state(AppState::Playing)));
+    }
+}
+
+// Which set of rules the current game is played with
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameMode {
+    Marathon { goal: MarathonGoal },
+    Sprint { target_lines: usize }, // Clear the lines as fast as possible
+    Ultra { seconds: u64 }, // Score as much as possible before the time runs out
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MarathonGoal {
+    Levels(usize), // Finish the last level, however many lines the level up rule needs for it
+    Lines(usize),
+    Endless,
+}
+
+impl GameMode {
+    // Every mode that can be picked from the menu, in order
+    pub const ALL: [GameMode; 7] = [
+        GameMode::Marathon { goal: MarathonGoal::Levels(15) },
+        GameMode::Marathon { goal: MarathonGoal::Lines(200) },
+        GameMode::Marathon { goal: MarathonGoal::Endless },
+        GameMode::Sprint { target_lines: 20 },
+        GameMode::Sprint { target_lines: 40 },
+        GameMode::Sprint { target_lines: 100 },
+        GameMode::Ultra { seconds: 120 },
+    ];
+
+    pub fn key(&self) -> String {
+        // Stored in save files, so never change an existing key
+        match self {
+            GameMode::Marathon { goal: MarathonGoal::Levels(levels) } => format!("marathon_levels_{}", levels),
+            GameMode::Marathon { goal: MarathonGoal::Lines(lines) } => format!("marathon_lines_{}", lines),
+            GameMode::Marathon { goal: MarathonGoal::Endless } => "marathon".to_string(),
+            GameMode::Sprint { target_lines } => format!("sprint_{}", target_lines),
+            GameMode::Ultra { seconds } => format!("ultra_{}", seconds),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            GameMode::Marathon { goal: MarathonGoal::Levels(levels) } => format!("Marathon {} Levels", levels),
+            GameMode::Marathon { goal: MarathonGoal::Lines(lines) } => format!("Marathon {} Lines", lines),
+            GameMode::Marathon { goal: MarathonGoal::Endless } => "Marathon Endless".to_string(),
+            GameMode::Sprint { target_lines } => format!("Sprint {}", target_lines),
+            GameMode::Ultra { seconds } => format!("Ultra {} min", seconds / 60),
+        }
+    }
+
+    pub fn goal(&self) -> ModeGoal {
+        match self {
+            GameMode::Marathon { goal: MarathonGoal::Levels(levels) } => ModeGoal::Level(*levels),
+            GameMode::Marathon { goal: MarathonGoal::Lines(lines) } => ModeGoal::Lines(*lines),
+            GameMode::Marathon { goal: MarathonGoal::Endless } => ModeGoal::None,
+            GameMode::Sprint { target_lines } => ModeGoal::Lines(*target_lines),
+            GameMode::Ultra { seconds } => ModeGoal::TimeLimit(Duration::from_secs(*seconds)),
+        }
+    }
+
+    // Highest level a marathon can reach, the goal ends the game there
+    pub fn level_cap(&self) -> Option<usize> {
+        match self {
+            GameMode::Marathon { goal: MarathonGoal::Levels(levels) } => Some(*levels),
+            GameMode::Marathon { goal: MarathonGoal::Lines(lines) } => Some(lines / 10),
+            _ => None,
+        }
+    }
+
+    // Shown over the board when the goal is reached
+    pub fn finish_text(&self) -> &'static str {
+        match self {
+            GameMode::Marathon { .. } => "Victory",
+            GameMode::Sprint { .. } => "Finished",
+            GameMode::Ultra { .. } => "Time Up",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let index = GameMode::ALL.iter().position(|mode| mode == self).unwrap_or(0);
+        GameMode::ALL[(index + 1) % GameMode::ALL.len()]
+    }
+
+    pub fn previous(&self) -> Self {
+        let index = GameMode::ALL.iter().position(|mode| mode == self).unwrap_or(0);
+        GameMode::ALL[(index + GameMode::ALL.len() - 1) % GameMode::ALL.len()]
+    }
+
+    pub fn ranks_by_time(&self) -> bool {
+        matches!(self, GameMode::Sprint { .. })
+    }
+
+    // Better entries sort first
+    pub fn compare_entries(&self, a: &HighScoreEntry, b: &HighScoreEntry) -> Ordering {
+        if self.ranks_by_time() {
+            a.duration.cmp(&b.duration)
+        } else {
+            b.score.cmp(&a.score)
+        }
+    }
+
+    // Whether a game that ended this way belongs on the high score table at all
+    pub fn is_rankable(&self, game_result: GameResult, score: usize) -> bool {
+        match self {
+            GameMode::Marathon { .. } => score > 0,
+            GameMode::Sprint { .. } => game_result == GameResult::Finished,
+            GameMode::Ultra { .. } => score > 0,
+        }
+    }
+}
+
+// Randomizer picked for each mode, a mode that was never changed deals from a 7-bag
+#[derive(Resource, Default)]
+pub struct ModeRandomizers(pub HashMap<GameMode, RandomizerKind>);
+impl ModeRandomizers {
+    pub fn for_mode(&self, game_mode: &GameMode) -> RandomizerKind {
+        self.0.get(game_mode).copied().unwrap_or(RandomizerKind::SevenBag)
+    }
+}
+
+const SPRINT_SPLIT_LINES: usize = 10;
+
+#[derive(Resource)]
+pub struct SprintProgress {
+    pub splits: Vec<Duration>, // Time when every 10 lines were reached
+}
+
+#[derive(Component)]
+pub struct ModeText;
+
+pub fn format_time_delta(time: Duration, best: Duration) -> String {
+    // +s.cc when slower than the best, -s.cc when faster
+    let (sign, delta) = if time >= best { ("+", time - best) } else { ("-", best - time) };
+    let centiseconds = delta.as_millis() / 10;
+    format!("{}{}.{:02}", sign, centiseconds / 100, centiseconds % 100)
+}
+
+pub fn personal_best<'a>(high_scores: &'a HighScores, game_mode: &GameMode) -> Option<&'a HighScoreEntry> {
+    high_scores.for_mode(game_mode).into_iter().min_by(|a, b| game_mode.compare_entries(a, b))
+}
+
+pub fn reset_sprint_progress(
+    mut sprint_progress: ResMut<SprintProgress>
+){
+    sprint_progress.splits.clear();
+}
+
+pub fn update_sprint_progress(
+    game_mode: Res<GameMode>,
+    game: Res<GameCore>,
+    mut sprint_progress: ResMut<SprintProgress>,
+){
+    let GameMode::Sprint { target_lines } = *game_mode else {
+        return;
+    };
+
+    // Runs after the game ticks so the frame the last line clears counts
+    let lines_cleared = game.scoring.lines_cleared.min(target_lines);
+    while sprint_progress.splits.len() < lines_cleared / SPRINT_SPLIT_LINES {
+        sprint_progress.splits.push(game.stats.play_time);
+    }
+}
+
+pub fn despawn_mode_text(
+    mut commands: Commands,
+    mode_text_query: Query<Entity, With<ModeText>>,
+){
+    for entity in mode_text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn sprint_splits_text(splits: &[Duration], best_splits: &[Duration]) -> String {
+    let mut text = String::new();
+    for (index, split) in splits.iter().enumerate() {
+        text.push_str(&format!("{}  {}", (index + 1) * SPRINT_SPLIT_LINES, format_play_time(*split)));
+        if let Some(best_split) = best_splits.get(index) {
+            text.push_str(&format!("  {}", format_time_delta(*split, *best_split)));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn draw_mode_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    grid_config: Res<GridConfig>,
+    game_mode: Res<GameMode>,
+    game: Res<GameCore>,
+    sprint_progress: Res<SprintProgress>,
+    high_scores: Res<HighScores>,
+    mut mode_text_query: Query<(&mut Text2d, &ModeTextPart)>,
+){
+    // Only modes with a goal have a timer to show
+    let game_stats = &game.stats;
+    let timer_text = match game_mode.goal() {
+        ModeGoal::None => return,
+        ModeGoal::Level(last_level) => format!("Time\n{}\nGoal: Level {}", format_play_time(game_stats.play_time), last_level),
+        ModeGoal::Lines(target_lines) => {
+            let lines_left = target_lines.saturating_sub(game.scoring.lines_cleared);
+            format!("Time\n{}\n{} lines left", format_play_time(game_stats.play_time), lines_left)
+        }
+        ModeGoal::TimeLimit(limit) => format!("Time Left\n{}", format_play_time(limit.saturating_sub(game_stats.play_time))),
+    };
+    let best_splits = personal_best(&high_scores, &game_mode).map_or(vec![], |best| best.splits.clone());
+    let splits_text = sprint_splits_text(&sprint_progress.splits, &best_splits);
+
+    // Spawned once per game, then only the text changes every frame
+    if mode_text_query.is_empty() {
+        let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+        let text_x = (grid_config.start_x + (GRID_WIDTH as f32 * GRID_CELL_SIZE)) + 300.0;
+        let text_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE) + 100.0;
+
+        commands.spawn((
+            Text2d::new(timer_text),
+            TextColor(Color::srgb(0.8, 0.85, 0.9)),
+            TextFont {
+                font: font.clone(),
+                font_size: 25.0,
+                ..default()
+            },
+            TextLayout::new_with_justify(JustifyText::Center),
+            Transform::from_xyz(text_x, text_y, 0.0),
+            ModeText {},
+            ModeTextPart::Timer
+        ));
+
+        // Splits hang down from the top of the grid
+        commands.spawn((
+            Text2d::new(splits_text),
+            TextColor(Color::srgb(0.8, 0.85, 0.9)),
+            TextFont {
+                font: font.clone(),
+                font_size: 18.0,
+                ..default()
+            },
+            TextLayout::new_with_justify(JustifyText::Center),
+            Anchor::TopCenter,
+            Transform::from_xyz(text_x, grid_config.start_y + (GRID_HEIGHT as f32 * GRID_CELL_SIZE), 0.0),
+            ModeText {},
+            ModeTextPart::Splits
+        ));
+        return;
+    }
+
+    for (mut text, part) in &mut mode_text_query {
+        text.0 = match part {
+            ModeTextPart::Timer => timer_text.clone(),
+            ModeTextPart::Splits => splits_text.clone(),
+        };
+    }
+}
+
+#[derive(Component)]
+pub enum ModeTextPart {
+    Timer,
+    Splits,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn draw_sprint_results(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    grid_config: Res<GridConfig>,
+    game_mode: Res<GameMode>,
+    game_result: Res<GameResult>,
+    game: Res<GameCore>,
+    sprint_progress: Res<SprintProgress>,
+    high_scores: Res<HighScores>,
+){
+    if !game_mode.ranks_by_time() {
+        return;
+    }
+
+    // Compared against the best from before this run, it hasn't been saved yet
+    let game_stats = &game.stats;
+    let best = personal_best(&high_scores, &game_mode);
+    let best_splits = best.map_or(vec![], |best| best.splits.clone());
+    let mut text = format!("{}\n\n", game_mode.name());
+    match (*game_result, best) {
+        (GameResult::Finished, Some(best)) if game_stats.play_time < best.duration => {
+            text.push_str(&format!("New Personal Best!\n{}\n\n", format_time_delta(game_stats.play_time, best.duration)));
+        }
+        (GameResult::Finished, Some(best)) => {
+            text.push_str(&format!("Personal Best {}\n{}\n\n", format_play_time(best.duration), format_time_delta(game_stats.play_time, best.duration)));
+        }
+        (GameResult::Finished, None) => text.push_str("New Personal Best!\n\n"),
+        (GameResult::Lost, _) => text.push_str("Not Finished\n\n"),
+    }
+    text.push_str("Splits\n");
+    text.push_str(&sprint_splits_text(&sprint_progress.splits, &best_splits));
+
+    // Over the scoring HUD on the right of the grid
+    let text_x = (grid_config.start_x + (GRID_WIDTH as f32 * GRID_CELL_SIZE)) + 300.0;
+    let text_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE);
+
+    commands.spawn((
+        Sprite::from_color(Color::srgba(0.05, 0.05, 0.1, 0.95), Vec2::new(380.0, 620.0)),
+        Transform::from_xyz(text_x, text_y, 50.0),
+        ResultsScreen {}
+    ));
+
+    commands.spawn((
+        Text2d::new(text),
+        TextColor(Color::srgb(0.8, 0.85, 0.9)),
+        TextFont {
+            font: asset_server.load("fonts/gg-sans-Regular.ttf"),
+            font_size: 22.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_xyz(text_x, text_y, 51.0),
+        ResultsScreen {}
+    ));
+}
@@ -0,0 +1,178 @@
+use std::env;
+
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+
+use crate::core::{Game, GameEvent, GameSettings, Input};
+use crate::game_manager::{AppState, GameLoseEvent, GameFinishEvent};
+use crate::grid::RedrawGridEvent;
+use crate::handling::AutoShift;
+use crate::hold::RedrawHeldPieceEvent;
+use crate::mode::{GameMode, ModeRandomizers};
+use crate::queue::PreviewConfig;
+use crate::scoring::{RedrawLevelAndScoreEvent, TSpinEvent, PerfectClearEvent};
+use crate::tetromino::{RedrawActivePieceEvent, RedrawNextPiecesEvent};
+use crate::controls::{Action, KeyBindings};
+
+pub struct PlayPlugin;
+impl Plugin for PlayPlugin{
+    fn build(&self, app: &mut App){
+        app
+            .insert_resource(GameCore(Game::new(GameSettings::default())))
+            .insert_resource(RuleSettings(GameSettings::default()))
+            .insert_resource(SeedConfig::from_args())
+            .add_systems(OnEnter(AppState::Countdown), new_game)
+            .add_systems(OnExit(AppState::Countdown), start_game)
+            .add_systems(Update, tick_game.run_if(in_state(AppState::Playing)));
+    }
+}
+
+// The game being played. Everything on screen is drawn from this, the plugins never change the rules themselves
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameCore(pub Game);
+
+// Rules picked in the menu, the mode fills in its goal when a game starts
+#[derive(Resource, Deref, DerefMut)]
+pub struct RuleSettings(pub GameSettings);
+
+// Seed for the next game, None rolls a new one every game
+#[derive(Resource)]
+pub struct SeedConfig {
+    pub seed: Option<u64>,
+}
+impl SeedConfig {
+    pub fn from_args() -> Self {
+        // --seed 1234 or --seed=1234 on the command line plays that seed until it's changed in the menu
+        let args: Vec<String> = env::args().collect();
+        let seed = args.iter().enumerate().find_map(|(index, arg)| {
+            if arg == "--seed" {
+                args.get(index + 1)?.parse().ok()
+            } else {
+                arg.strip_prefix("--seed=")?.parse().ok()
+            }
+        });
+        SeedConfig { seed }
+    }
+}
+
+// Every front end event a GameEvent can turn into
+#[derive(SystemParam)]
+pub struct GameEventWriters<'w> {
+    redraw_grid: EventWriter<'w, RedrawGridEvent>,
+    redraw_active_piece: EventWriter<'w, RedrawActivePieceEvent>,
+    redraw_next_pieces: EventWriter<'w, RedrawNextPiecesEvent>,
+    redraw_held_piece: EventWriter<'w, RedrawHeldPieceEvent>,
+    redraw_level_and_score: EventWriter<'w, RedrawLevelAndScoreEvent>,
+    t_spin: EventWriter<'w, TSpinEvent>,
+    perfect_clear: EventWriter<'w, PerfectClearEvent>,
+    game_lose: EventWriter<'w, GameLoseEvent>,
+    game_finish: EventWriter<'w, GameFinishEvent>,
+}
+impl GameEventWriters<'_> {
+    fn send(&mut self, events: Vec<GameEvent>) {
+        for event in events {
+            match event {
+                GameEvent::PieceSpawned(_) => {
+                    self.redraw_next_pieces.send(RedrawNextPiecesEvent);
+                }
+                GameEvent::PieceMoved => {
+                    self.redraw_active_piece.send(RedrawActivePieceEvent);
+                }
+                GameEvent::PieceHeld(_) => {
+                    self.redraw_held_piece.send(RedrawHeldPieceEvent);
+                    self.redraw_active_piece.send(RedrawActivePieceEvent);
+                }
+                GameEvent::PieceLocked { .. } => {
+                    self.redraw_grid.send(RedrawGridEvent);
+                    self.redraw_active_piece.send(RedrawActivePieceEvent);
+                }
+                GameEvent::TSpin { t_spin, lines_cleared } => {
+                    self.t_spin.send(TSpinEvent { t_spin, lines_cleared });
+                }
+                GameEvent::PerfectClear { lines_cleared, back_to_back } => {
+                    self.perfect_clear.send(PerfectClearEvent { lines_cleared, back_to_back });
+                }
+                GameEvent::ScoreChanged | GameEvent::LevelUp(_) => {
+                    self.redraw_level_and_score.send(RedrawLevelAndScoreEvent);
+                }
+                GameEvent::ToppedOut => {
+                    self.game_lose.send(GameLoseEvent);
+                }
+                GameEvent::GoalReached => {
+                    self.game_finish.send(GameFinishEvent);
+                }
+            }
+        }
+    }
+}
+
+pub fn new_game(
+    mut game: ResMut<GameCore>,
+    rule_settings: Res<RuleSettings>,
+    seed_config: Res<SeedConfig>,
+    game_mode: Res<GameMode>,
+    mode_randomizers: Res<ModeRandomizers>,
+    preview_config: Res<PreviewConfig>,
+    mut game_event_writers: GameEventWriters,
+){
+    // A fresh board for the countdown, the first piece only spawns once it's over
+    let mut settings = rule_settings.0.clone();
+    settings.goal = game_mode.goal();
+    settings.level_cap = game_mode.level_cap();
+    settings.randomizer = mode_randomizers.for_mode(&game_mode);
+    settings.preview_count = preview_config.count;
+    settings.seed = seed_config.seed.unwrap_or_else(rand::random);
+    game.0 = Game::new(settings);
+
+    game_event_writers.redraw_grid.send(RedrawGridEvent);
+    game_event_writers.redraw_active_piece.send(RedrawActivePieceEvent);
+    game_event_writers.redraw_held_piece.send(RedrawHeldPieceEvent);
+    game_event_writers.redraw_level_and_score.send(RedrawLevelAndScoreEvent);
+}
+
+pub fn start_game(
+    mut game: ResMut<GameCore>,
+    mut game_event_writers: GameEventWriters,
+){
+    let events = game.start();
+    game_event_writers.send(events);
+}
+
+pub fn tick_game(
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    auto_shift: Res<AutoShift>,
+    key_bindings: Res<KeyBindings>,
+    mut game: ResMut<GameCore>,
+    mut game_event_writers: GameEventWriters,
+){
+    // Turn this frame's keys into inputs in the order the game should apply them
+    let mut inputs = vec![];
+    if auto_shift.pending_shifts != 0 {
+        inputs.push(Input::Shift(auto_shift.pending_shifts));
+    }
+    if auto_shift.pending_soft_drops > 0 {
+        inputs.push(Input::SoftDrop(auto_shift.pending_soft_drops));
+    }
+    if key_bindings.just_pressed(&keyboard_input, Action::RotateClockwise) {
+        inputs.push(Input::RotateClockwise);
+    }
+    if key_bindings.just_pressed(&keyboard_input, Action::RotateCounterClockwise) {
+        inputs.push(Input::RotateCounterClockwise);
+    }
+    if key_bindings.just_pressed(&keyboard_input, Action::Rotate180) {
+        inputs.push(Input::Rotate180);
+    }
+    if key_bindings.just_pressed(&keyboard_input, Action::HardDrop) {
+        inputs.push(Input::HardDrop);
+    }
+    if key_bindings.just_pressed(&keyboard_input, Action::SonicDrop) {
+        inputs.push(Input::SonicDrop);
+    }
+    if key_bindings.just_pressed(&keyboard_input, Action::Hold) {
+        inputs.push(Input::Hold);
+    }
+
+    let events = game.tick(time.delta(), &inputs);
+    game_event_writers.send(events);
+}
\ No newline at end of file
@@ -1,23 +1,25 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
+use crate::core::TSpin;
 use crate::grid::{GridConfig, GRID_WIDTH, GRID_CELL_SIZE, GRID_HEIGHT};
-use crate::game_manager::GameStartEvent;
+use crate::play::{GameCore, tick_game};
 
 pub struct ScoringPlugin;
 impl Plugin for ScoringPlugin{
     fn build(&self, app: &mut App){
         app
-            .insert_resource(Scoring{level: 1, score: 0, lines_cleared: 0})
             .add_event::<RedrawLevelAndScoreEvent>()
-            .add_event::<LevelUpEvent>()
-            .add_systems(Update, (draw_level_and_score, reset_level_and_score));
+            .add_event::<TSpinEvent>()
+            .add_event::<PerfectClearEvent>()
+            .add_systems(Update, (draw_level_and_score, draw_t_spin_banner, draw_perfect_clear_banner, fade_scoring_banners).after(tick_game));
     }
 }
 
-#[derive(Resource)]
-pub struct Scoring{
-    pub level: usize,
-    pub score: usize,
-    pub lines_cleared: usize 
+pub fn format_play_time(play_time: Duration) -> String {
+    // m:ss.cc
+    let centiseconds = play_time.as_millis() / 10;
+    format!("{}:{:02}.{:02}", centiseconds / 6000, (centiseconds / 100) % 60, centiseconds % 100)
 }
 
 #[derive(Component)]
@@ -27,32 +29,44 @@ pub struct ScoringText {}
 pub struct RedrawLevelAndScoreEvent;
 
 #[derive(Event)]
-pub struct LevelUpEvent;
+pub struct TSpinEvent {
+    pub t_spin: TSpin,
+    pub lines_cleared: usize,
+}
+
+#[derive(Event)]
+pub struct PerfectClearEvent {
+    pub lines_cleared: usize,
+    pub back_to_back: bool, // A tetris perfect clear that continued a back-to-back chain
+}
+
+#[derive(Component)]
+pub struct ScoringBanner {
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct TSpinBanner;
+
+#[derive(Component)]
+pub struct PerfectClearBanner;
 
 pub fn draw_level_and_score(
     mut commands: Commands,
-    mut scoring_resource: ResMut<Scoring>,
+    game: Res<GameCore>,
     asset_server: Res<AssetServer>,
     grid_config: Res<GridConfig>,
     mut redraw_level_and_score_event: EventReader<RedrawLevelAndScoreEvent>,
-    mut game_start_event: EventReader<GameStartEvent>,
     scoring_text_query: Query<(Entity, &ScoringText)>,
-    mut level_up_event: EventWriter<LevelUpEvent> 
 ){
-    if !redraw_level_and_score_event.is_empty() || !game_start_event.is_empty(){
+    if !redraw_level_and_score_event.is_empty(){
         redraw_level_and_score_event.clear();
-        game_start_event.clear();
 
         for (entity, _) in scoring_text_query.iter(){
             commands.entity(entity).despawn();
         }
 
-        // Calculate level
-        let old_level = scoring_resource.level;
-        scoring_resource.level = calculate_level(&scoring_resource.lines_cleared);
-        if old_level < scoring_resource.level{
-            level_up_event.send(LevelUpEvent);
-        }
+        let scoring_resource = &game.scoring;
 
         let font = asset_server.load("fonts/gg-sans-Regular.ttf");
         let text_font = TextFont {
@@ -63,7 +77,7 @@ pub fn draw_level_and_score(
         let text_color = TextColor(Color::srgb(0.8, 0.85, 0.9));
 
         // Draw Level
-        let text_x = (grid_config.start_x + (GRID_WIDTH as f32 * GRID_CELL_SIZE)) + 100.0;
+        let text_x = (grid_config.start_x + (GRID_WIDTH as f32 * GRID_CELL_SIZE)) + 300.0;
         let text_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE);
 
         commands.spawn((
@@ -76,7 +90,7 @@ pub fn draw_level_and_score(
         ));
 
         // Draw Score 
-        let text_x = (grid_config.start_x + (GRID_WIDTH as f32 * GRID_CELL_SIZE)) + 100.0;
+        let text_x = (grid_config.start_x + (GRID_WIDTH as f32 * GRID_CELL_SIZE)) + 300.0;
         let text_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE) - 75.0;
 
         commands.spawn((
@@ -89,7 +103,7 @@ pub fn draw_level_and_score(
         ));
 
         // Total Lines Cleared
-        let text_x = (grid_config.start_x + (GRID_WIDTH as f32 * GRID_CELL_SIZE)) + 100.0;
+        let text_x = (grid_config.start_x + (GRID_WIDTH as f32 * GRID_CELL_SIZE)) + 300.0;
         let text_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE) - 150.0;
 
         commands.spawn((
@@ -101,40 +115,141 @@ pub fn draw_level_and_score(
             ScoringText {}
         ));
 
+        // Combo and back-to-back chain, only shown while they're going
+        let text_x = (grid_config.start_x + (GRID_WIDTH as f32 * GRID_CELL_SIZE)) + 300.0;
+        let mut text_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE) - 225.0;
+
+        if let Some(combo) = scoring_resource.combo.filter(|combo| *combo > 0) {
+            commands.spawn((
+                Text2d::new(format!("Combo\n{}", combo)),
+                text_font.clone(),
+                text_color,
+                TextLayout::new_with_justify(JustifyText::Center),
+                Transform::from_xyz(text_x, text_y, 0.0),
+                ScoringText {}
+            ));
+            text_y -= 75.0;
+        }
 
+        if let Some(back_to_back) = scoring_resource.back_to_back.filter(|back_to_back| *back_to_back > 0) {
+            commands.spawn((
+                Text2d::new(format!("Back-to-Back\n{}", back_to_back)),
+                text_font.clone(),
+                text_color,
+                TextLayout::new_with_justify(JustifyText::Center),
+                Transform::from_xyz(text_x, text_y, 0.0),
+                ScoringText {}
+            ));
+        }
     }
 }
 
-pub fn calculate_level(total_lines_cleared: &usize) -> usize {
-    (total_lines_cleared / 10) + 1
+pub fn t_spin_banner_text(t_spin: TSpin, lines_cleared: usize) -> String {
+    let spin = match t_spin {
+        TSpin::Mini => "T-SPIN MINI",
+        _ => "T-SPIN",
+    };
+    match lines_cleared {
+        1 => format!("{} SINGLE", spin),
+        2 => format!("{} DOUBLE", spin),
+        3 => format!("{} TRIPLE", spin),
+        _ => spin.to_string(),
+    }
 }
 
-pub fn calculate_score(lines_cleared_at_once: usize, level: usize) -> usize {
-    match lines_cleared_at_once {
-        1 => 100 * level,
-        2 => 300 * level,
-        3 => 500 * level,
-        4 => 800 * level,
-        _ => 0 // Should never happen
+pub fn draw_t_spin_banner(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    grid_config: Res<GridConfig>,
+    mut t_spin_event: EventReader<TSpinEvent>,
+    t_spin_banner_query: Query<Entity, With<TSpinBanner>>,
+){
+    for event in t_spin_event.read() {
+        // Only the latest banner is shown
+        for entity in t_spin_banner_query.iter() {
+            commands.entity(entity).despawn();
+        }
+
+        let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+        let text_font = TextFont {
+            font: font.clone(),
+            font_size: 30.0,
+            ..default()
+        };
+
+        // Draw to the right of the grid, under the preview column
+        let text_x = (grid_config.start_x + (GRID_WIDTH as f32 * GRID_CELL_SIZE)) + 130.0;
+        let text_y = grid_config.start_y + 100.0;
+
+        commands.spawn((
+            Text2d::new(t_spin_banner_text(event.t_spin, event.lines_cleared)),
+            TextColor(Color::srgb(0.7, 0.3, 0.8)),
+            text_font.clone(),
+            TextLayout::new_with_justify(JustifyText::Center),
+            Transform::from_xyz(text_x, text_y, 0.0),
+            ScoringBanner { timer: Timer::from_seconds(1.5, TimerMode::Once) },
+            TSpinBanner
+        ));
     }
 }
 
-pub fn reset_level_and_score(
-    mut game_start_event: EventReader<GameStartEvent>,
-    mut redraw_level_and_score_event: EventWriter<RedrawLevelAndScoreEvent>,
-    mut scoring_resource: ResMut<Scoring>
+pub fn draw_perfect_clear_banner(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    grid_config: Res<GridConfig>,
+    mut perfect_clear_event: EventReader<PerfectClearEvent>,
+    perfect_clear_banner_query: Query<Entity, With<PerfectClearBanner>>,
 ){
-    // Receive Game Start Event and reset score
-    if !game_start_event.is_empty(){
-        game_start_event.clear();
+    for event in perfect_clear_event.read() {
+        for entity in perfect_clear_banner_query.iter() {
+            commands.entity(entity).despawn();
+        }
 
-        // Reset score 
-        scoring_resource.level = 0;
-        scoring_resource.score = 0;
-        scoring_resource.lines_cleared = 0;
+        let font = asset_server.load("fonts/gg-sans-Regular.ttf");
+        let text_font = TextFont {
+            font: font.clone(),
+            font_size: 60.0,
+            ..default()
+        };
 
-        // Send event to redraw the level and score 
-        redraw_level_and_score_event.send(RedrawLevelAndScoreEvent);
+        // Draw across the middle of the grid, it's empty anyway
+        let text_x = grid_config.start_x + ((GRID_WIDTH as f32 / 2.0) * GRID_CELL_SIZE);
+        let text_y = grid_config.start_y + ((GRID_HEIGHT as f32 / 2.0) * GRID_CELL_SIZE);
+        let clear = match event.lines_cleared {
+            1 => "SINGLE",
+            2 => "DOUBLE",
+            3 => "TRIPLE",
+            _ => "TETRIS",
+        };
+        let text = if event.back_to_back {
+            format!("B2B {}\nPERFECT CLEAR", clear)
+        } else {
+            format!("{}\nPERFECT CLEAR", clear)
+        };
+
+        commands.spawn((
+            Text2d::new(text),
+            TextColor(Color::srgb(1.0, 0.85, 0.2)),
+            text_font.clone(),
+            TextLayout::new_with_justify(JustifyText::Center),
+            Transform::from_xyz(text_x, text_y, 1.0),
+            ScoringBanner { timer: Timer::from_seconds(2.0, TimerMode::Once) },
+            PerfectClearBanner
+        ));
     }
+}
 
+pub fn fade_scoring_banners(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut scoring_banner_query: Query<(Entity, &mut ScoringBanner, &mut TextColor)>,
+){
+    for (entity, mut banner, mut text_color) in &mut scoring_banner_query {
+        banner.timer.tick(time.delta());
+        if banner.timer.finished() {
+            commands.entity(entity).despawn();
+        } else {
+            text_color.0.set_alpha(1.0 - banner.timer.fraction());
+        }
+    }
 }
\ No newline at end of file
@@ -1,5 +1,8 @@
 use bevy::prelude::*;
 use crate::grid::{GridConfig, GRID_HEIGHT, GRID_CELL_SIZE, GRID_HIDDEN_HEIGHT};
+use crate::controls::{Action, KeyBindings};
+use crate::game_manager::AppState;
+use crate::high_scores::is_entering_name;
  
 pub struct TipsPlugin;
 impl Plugin for TipsPlugin{
@@ -7,7 +10,10 @@ impl Plugin for TipsPlugin{
         app
             .add_event::<DrawGameTipsEvent>()
             .add_systems(Startup, setup)
-            .add_systems(Update, (toggle_game_tips, draw_game_tips));
+            .add_systems(OnExit(AppState::Controls), refresh_game_tips)
+            // Letters typed into a high score name or picked as a new binding aren't tip toggles
+            .add_systems(Update, toggle_game_tips.run_if(not(is_entering_name).and(not(in_state(AppState::Controls)))))
+            .add_systems(Update, draw_game_tips);
     }
 }
 
@@ -28,6 +34,7 @@ pub fn draw_game_tips(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     grid_config: Res<GridConfig>,
+    key_bindings: Res<KeyBindings>,
     mut draw_game_tips_event: EventReader<DrawGameTipsEvent>,
 ){
     if !draw_game_tips_event.is_empty(){
@@ -41,15 +48,21 @@ pub fn draw_game_tips(
         };
         let text_color = TextColor(Color::srgb(0.8, 0.85, 0.9));
 
-        let help_texts= vec![
-            "ENTER to start game",
-            "Left/Right Arrow to move",
-            "Down Arrow to drop",
-            "Up Arrow to rotate clockwise",
-            "CTRL to rotate counter clockwise",
-            "SPACE to hard drop",
-            "R to reset",
-            "H to hide this text"
+        // Written from the current bindings so they always match the keys
+        let describe = |action: Action| key_bindings.describe(action);
+        let help_texts = vec![
+            "ENTER to start game".to_string(),
+            format!("{} / {} to move", describe(Action::MoveLeft), describe(Action::MoveRight)),
+            format!("{} to drop", describe(Action::SoftDrop)),
+            format!("{} to rotate clockwise", describe(Action::RotateClockwise)),
+            format!("{} to rotate counter clockwise", describe(Action::RotateCounterClockwise)),
+            format!("{} to rotate 180", describe(Action::Rotate180)),
+            format!("{} to hard drop", describe(Action::HardDrop)),
+            format!("{} to sonic drop", describe(Action::SonicDrop)),
+            format!("{} to hold", describe(Action::Hold)),
+            format!("{} to pause", describe(Action::Pause)),
+            format!("{} to reset", describe(Action::Restart)),
+            format!("{} to hide this text", describe(Action::ToggleTips)),
             ];
 
         let text_x = grid_config.start_x - 150.0;
@@ -74,10 +87,11 @@ pub fn draw_game_tips(
 pub fn toggle_game_tips(
     mut commands: Commands,
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
     game_tip_text_query: Query<Entity, With<GameTipText>>,
     mut draw_game_tips_event: EventWriter<DrawGameTipsEvent>
 ){
-    if keyboard_input.just_pressed(KeyCode::KeyH) {
+    if key_bindings.just_pressed(&keyboard_input, Action::ToggleTips) {
         // draw game tips
         if game_tip_text_query.is_empty(){
             draw_game_tips_event.send(DrawGameTipsEvent);
@@ -87,4 +101,18 @@ pub fn toggle_game_tips(
             }
         }
     }
+}
+
+pub fn refresh_game_tips(
+    mut commands: Commands,
+    game_tip_text_query: Query<Entity, With<GameTipText>>,
+    mut draw_game_tips_event: EventWriter<DrawGameTipsEvent>
+){
+    // Rewrite the tips with the new bindings, unless they're hidden
+    if !game_tip_text_query.is_empty() {
+        for entity in game_tip_text_query.iter(){
+            commands.entity(entity).despawn();
+        }
+        draw_game_tips_event.send(DrawGameTipsEvent);
+    }
 }
\ No newline at end of file